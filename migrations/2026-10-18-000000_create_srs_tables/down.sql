@@ -0,0 +1,3 @@
+DROP TABLE review_records;
+DROP TABLE card_states;
+DROP TABLE learners;
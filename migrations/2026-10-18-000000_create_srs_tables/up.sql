@@ -0,0 +1,35 @@
+CREATE TABLE learners (
+    id BIGSERIAL PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+-- `list` is a vocabulary source name (gcse, asvocab, clc) and `entry_id`
+-- the id of a row in that source's table.
+CREATE TABLE card_states (
+    id BIGSERIAL PRIMARY KEY,
+    learner_id BIGINT NOT NULL REFERENCES learners (id) ON DELETE CASCADE,
+    list VARCHAR NOT NULL,
+    entry_id BIGINT NOT NULL,
+    repetitions INTEGER NOT NULL DEFAULT 0,
+    interval_days INTEGER NOT NULL DEFAULT 0,
+    ease_factor DOUBLE PRECISION NOT NULL DEFAULT 2.5,
+    due_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP NOT NULL DEFAULT now(),
+    UNIQUE (learner_id, list, entry_id)
+);
+
+CREATE INDEX card_states_due ON card_states (learner_id, list, due_at);
+
+CREATE TABLE review_records (
+    id BIGSERIAL PRIMARY KEY,
+    learner_id BIGINT NOT NULL REFERENCES learners (id) ON DELETE CASCADE,
+    list VARCHAR NOT NULL,
+    entry_id BIGINT NOT NULL,
+    grade SMALLINT NOT NULL CHECK (grade BETWEEN 0 AND 5),
+    reviewed_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP NOT NULL DEFAULT now()
+);
@@ -0,0 +1 @@
+DROP TABLE lns_entries;
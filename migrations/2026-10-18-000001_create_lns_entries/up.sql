@@ -0,0 +1,17 @@
+-- One row per Lewis & Short entry, keyed by the `id` of its <div2>, which
+-- is also the `xml_id` in lns_entry_keys. `early_i_tags` and `senses` hold
+-- the parsed entry as JSON; `raw_xml` is the entry's line as it was read.
+CREATE TABLE lns_entries (
+    id BIGSERIAL PRIMARY KEY,
+    xml_id VARCHAR NOT NULL UNIQUE,
+    key VARCHAR NOT NULL,
+    head VARCHAR NOT NULL,
+    orth_orig VARCHAR NOT NULL,
+    early_i_tags JSONB NOT NULL DEFAULT '[]',
+    senses JSONB NOT NULL DEFAULT '[]',
+    raw_xml TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX lns_entries_key ON lns_entries (key);
@@ -0,0 +1,7 @@
+DROP INDEX wwords_folded_headword;
+DROP INDEX asvocab_folded_headword;
+DROP INDEX clc4_folded_headword;
+DROP INDEX gcse_latin_folded_headword;
+DROP INDEX lns_entry_keys_folded_head;
+DROP INDEX lewis_short_lemmata_folded_form;
+DROP FUNCTION latin_fold(text);
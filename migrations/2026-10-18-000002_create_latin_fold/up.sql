@@ -0,0 +1,21 @@
+-- The spelling lookups compare on, as `normalize` in src/normalize.rs
+-- computes it: trimmed, lowercase, without combining diacritics, and with
+-- the letters of FOLD_FROM translated to those of FOLD_TO. Keep the two
+-- strings here in step with the constants there.
+CREATE FUNCTION latin_fold(text) RETURNS text AS $$
+    SELECT translate(
+        regexp_replace(lower(btrim($1, E' \t\r\n')), '[\u0300-\u036f]', '', 'g'),
+        'jvāēīōūȳăĕĭŏŭëï',
+        'iuaeiouyaeiouei'
+    )
+$$ LANGUAGE SQL IMMUTABLE STRICT PARALLEL SAFE;
+
+-- text_pattern_ops serves both the equality lookups and the prefix LIKEs
+-- of `suggest`, whatever the database's collation.
+CREATE INDEX lewis_short_lemmata_folded_form
+    ON lewis_short_lemmata (latin_fold(form) text_pattern_ops);
+CREATE INDEX lns_entry_keys_folded_head ON lns_entry_keys (latin_fold(head));
+CREATE INDEX gcse_latin_folded_headword ON gcse_latin (latin_fold(headword) text_pattern_ops);
+CREATE INDEX clc4_folded_headword ON clc4 (latin_fold(headword) text_pattern_ops);
+CREATE INDEX asvocab_folded_headword ON asvocab (latin_fold(headword) text_pattern_ops);
+CREATE INDEX wwords_folded_headword ON wwords (latin_fold(headword) text_pattern_ops);
@@ -0,0 +1,5 @@
+DROP INDEX wwords_fuzzy;
+DROP INDEX asvocab_fuzzy;
+DROP INDEX clc4_fuzzy;
+DROP INDEX gcse_latin_fuzzy;
+DROP INDEX lewis_short_lemmata_fuzzy;
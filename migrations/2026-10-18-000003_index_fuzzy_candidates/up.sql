@@ -0,0 +1,8 @@
+-- The candidates `did_you_mean` in src/fuzzy.rs considers: words whose
+-- folded first letter is one of a few, within a range of lengths.
+CREATE INDEX lewis_short_lemmata_fuzzy
+    ON lewis_short_lemmata (left(latin_fold(form), 1), length(form));
+CREATE INDEX gcse_latin_fuzzy ON gcse_latin (left(latin_fold(headword), 1), length(headword));
+CREATE INDEX clc4_fuzzy ON clc4 (left(latin_fold(headword), 1), length(headword));
+CREATE INDEX asvocab_fuzzy ON asvocab (left(latin_fold(headword), 1), length(headword));
+CREATE INDEX wwords_fuzzy ON wwords (left(latin_fold(headword), 1), length(headword));
@@ -0,0 +1,352 @@
+use crate::enclitics::{split_enclitics, Compound};
+use crate::error::LatinDbError;
+use crate::fuzzy::did_you_mean;
+use crate::lns_store::{load_entries, EntryKey};
+use crate::models::{group_hits, LookupResult, VocabHit};
+use crate::normalize::{fold_sql, search_key};
+use crate::options::QueryOptions;
+use crate::sources::VOCAB_SOURCES;
+use crate::{lookup_form_threaded, DbPool};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::sql_types::{Array, BigInt, Nullable, Text};
+use std::collections::HashMap;
+
+/// The name `lns_entry_keys` rows are tagged with in the union.
+const LNS_SOURCE: &str = "lns";
+
+#[derive(QueryableByName, Debug)]
+struct AggregateRow {
+    #[sql_type = "Text"]
+    term: String,
+    #[sql_type = "Text"]
+    source: String,
+    #[sql_type = "BigInt"]
+    id: i64,
+    #[sql_type = "Text"]
+    headword: String,
+    #[sql_type = "Text"]
+    dict_form: String,
+    #[sql_type = "Nullable<Text>"]
+    part_of_speech: Option<String>,
+    #[sql_type = "Nullable<Text>"]
+    class: Option<String>,
+    #[sql_type = "Nullable<Text>"]
+    meaning: Option<String>,
+    #[sql_type = "Text"]
+    analysis: String,
+}
+
+/// Build the `UNION ALL` over every registered source, and optionally the
+/// LNS keys, joined against the headwords each of the forms in `$1`
+/// resolves to. Unless `options.exact`, the forms are folded as by
+/// `normalize` and `term` is the folded form. The vocabulary rows are
+/// filtered by `options`; the LNS keys are not.
+fn form_lookup_sql(with_lns: bool, options: &QueryOptions) -> String {
+    let filter = options.filter_sql("h.analysis", Some("v.part_of_speech"));
+    let mut branches: Vec<String> = VOCAB_SOURCES
+        .iter()
+        .map(|source| {
+            format!(
+                "SELECT h.form AS term, '{}' AS source, v.id, v.headword, v.dict_form, \
+                 v.part_of_speech, v.class, v.meaning, h.analysis FROM ({}) v JOIN heads h ON v.headword = h.headword \
+                 WHERE {}",
+                source.name(),
+                source.sql_select(),
+                filter
+            )
+        })
+        .collect();
+    if with_lns {
+        // LNS rows carry the entry's key in `dict_form` and its xml_id in
+        // `class`. Every entry sharing a `simple_key` with a match is
+        // included, so homographs come back together.
+        branches.push(format!(
+            "SELECT h.form AS term, '{}' AS source, k.id, k.head AS headword, k.key AS dict_form, \
+             NULL::varchar AS part_of_speech, k.xml_id AS class, NULL::varchar AS meaning, \
+             h.analysis FROM lns_entry_keys m JOIN heads h ON m.head = h.headword \
+             JOIN lns_entry_keys k ON k.simple_key = m.simple_key",
+            LNS_SOURCE
+        ));
+    }
+    let form = if options.exact {
+        "form".to_string()
+    } else {
+        fold_sql("form")
+    };
+    format!(
+        "WITH heads AS (SELECT {form} AS form, headword, analysis FROM lewis_short_lemmata \
+         WHERE {form} = ANY($1)) {} \
+         ORDER BY term, source, headword, dict_form, id, analysis",
+        branches.join(" UNION ALL "),
+        form = form
+    )
+}
+
+/// Run the union for `terms` and split the rows back out per term, in the
+/// order the terms first appear. Repeated terms are only looked up once,
+/// though spellings that only normalize to the same key are kept apart.
+fn run_lookup(
+    terms: &[&str],
+    with_lns: bool,
+    options: &QueryOptions,
+    connection: &PgConnection,
+) -> Result<Vec<(String, LookupResult)>, LatinDbError> {
+    let mut unique: Vec<&str> = Vec::new();
+    let mut positions: HashMap<&str, usize> = HashMap::new();
+    for term in terms {
+        if !positions.contains_key(term) {
+            positions.insert(term, unique.len());
+            unique.push(term);
+        }
+    }
+
+    let mut by_key: HashMap<String, Vec<usize>> = HashMap::new();
+    for (index, term) in unique.iter().enumerate() {
+        by_key
+            .entry(search_key(term, options.exact))
+            .or_default()
+            .push(index);
+    }
+    let keys: Vec<&String> = by_key.keys().collect();
+
+    let rows: Vec<AggregateRow> = diesel::sql_query(form_lookup_sql(with_lns, options))
+        .bind::<Array<Text>, _>(&keys)
+        .load(connection)?;
+
+    let mut results: Vec<(String, LookupResult)> = unique
+        .iter()
+        .map(|term| {
+            let mut result = LookupResult::default();
+            for source in VOCAB_SOURCES {
+                result.vocab.insert(source.name().to_string(), Vec::new());
+            }
+            (term.to_string(), result)
+        })
+        .collect();
+    let mut lns_keys: Vec<Vec<EntryKey>> = vec![Vec::new(); unique.len()];
+
+    for row in rows {
+        let indices = match by_key.get(&row.term) {
+            Some(indices) => indices,
+            None => continue,
+        };
+        if row.source == LNS_SOURCE {
+            let entry_key = EntryKey {
+                xml_id: row.class.unwrap_or_default(),
+                key: row.dict_form,
+            };
+            for &index in indices {
+                if !lns_keys[index].contains(&entry_key) {
+                    lns_keys[index].push(entry_key.clone());
+                }
+            }
+            continue;
+        }
+        let hit = VocabHit {
+            id: row.id,
+            headword: row.headword,
+            dict_form: row.dict_form,
+            part_of_speech: row.part_of_speech,
+            class: row.class,
+            meaning: row.meaning.unwrap_or_default(),
+            analyses: Vec::new(),
+        }
+        .with_analysis(&row.analysis);
+        for &index in indices {
+            results[index]
+                .1
+                .vocab
+                .entry(row.source.clone())
+                .or_default()
+                .push(hit.clone());
+        }
+    }
+
+    for (_, result) in results.iter_mut() {
+        for hits in result.vocab.values_mut() {
+            *hits = group_hits(std::mem::take(hits));
+        }
+    }
+
+    if with_lns {
+        for ((_, result), keys) in results.iter_mut().zip(lns_keys) {
+            match load_entries(&keys, connection) {
+                Ok(entries) => result.lns = entries,
+                Err(e) => {
+                    result.errors.insert(LNS_SOURCE.to_string(), e.to_string());
+                }
+            }
+        }
+    }
+    Ok(results)
+}
+
+/// Retry every term that matched nothing with its enclitic split off, in one
+/// more call to `lookup`, and keep the first split that finds something.
+fn resolve_enclitics<F>(
+    results: &mut [(String, LookupResult)],
+    lookup: F,
+) -> Result<(), LatinDbError>
+where
+    F: Fn(&[&str]) -> Result<Vec<(String, LookupResult)>, LatinDbError>,
+{
+    let splits: Vec<(usize, Vec<Compound>)> = results
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, result))| result.is_empty() && result.errors.is_empty())
+        .map(|(index, (term, _))| (index, split_enclitics(term)))
+        .filter(|(_, compounds)| !compounds.is_empty())
+        .collect();
+    if splits.is_empty() {
+        return Ok(());
+    }
+
+    let stems: Vec<&str> = splits
+        .iter()
+        .flat_map(|(_, compounds)| compounds.iter().map(|c| c.stem.as_str()))
+        .collect();
+    let found: HashMap<String, LookupResult> = lookup(&stems)?
+        .into_iter()
+        .filter(|(_, result)| !result.is_empty())
+        .collect();
+
+    for (index, compounds) in splits {
+        for compound in compounds {
+            if let Some(stem_result) = found.get(&compound.stem) {
+                let mut result = stem_result.clone();
+                result.compound = Some(compound);
+                results[index].1 = result;
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Look `term` up with `lookup`, then retry it without an enclitic and fall
+/// back to suggestions as described on `lookup_form`.
+fn resolve_form<F>(
+    term: &str,
+    options: &QueryOptions,
+    lookup: F,
+    connection: &PgConnection,
+) -> Result<LookupResult, LatinDbError>
+where
+    F: Fn(&[&str]) -> Result<Vec<(String, LookupResult)>, LatinDbError>,
+{
+    let mut results = lookup(&[term])?;
+    resolve_enclitics(&mut results, &lookup)?;
+    let mut result = results.pop().map(|(_, result)| result).unwrap_or_default();
+    if result.is_empty() && result.errors.is_empty() && options.is_empty() {
+        result.suggestions = did_you_mean(term, options.exact, connection)?;
+    }
+    Ok(result)
+}
+
+/// Look `term` up in every source with a single query on one connection.
+///
+/// The LNS keys come back in the same round trip; the entries themselves
+/// are loaded afterwards by `load_entries` (from the cache, then
+/// `lns_entries`, then the XML files), and a failure there is reported
+/// under `errors` like in the threaded lookup. `options` filters the
+/// vocabulary hits but not the LNS entries.
+///
+/// A term that matches nothing is retried without an enclitic (`-que`,
+/// `-ne`, `-ve`, or `-cum` after a pronoun), and the split is reported in
+/// `compound`. If that fails too and the lookup is unfiltered, the nearest
+/// known forms are offered in `suggestions`.
+pub fn lookup_form(
+    term: &str,
+    options: &QueryOptions,
+    connection: &PgConnection,
+) -> Result<LookupResult, LatinDbError> {
+    resolve_form(
+        term,
+        options,
+        |terms| run_lookup(terms, true, options, connection),
+        connection,
+    )
+}
+
+/// As `lookup_form`, but asking each source on its own pooled connection
+/// and thread with `lookup_form_threaded`. Enclitics and suggestions are
+/// handled the same way.
+pub fn resolve_form_threaded(
+    term: &str,
+    options: &QueryOptions,
+    pool: &DbPool,
+) -> Result<LookupResult, LatinDbError> {
+    let connection = pool.get()?;
+    resolve_form(
+        term,
+        options,
+        |terms| {
+            Ok(terms
+                .iter()
+                .map(|term| {
+                    let result = LookupResult::from(lookup_form_threaded(term, options, pool));
+                    (term.to_string(), result)
+                })
+                .collect())
+        },
+        &connection,
+    )
+}
+
+/// Look up many forms at once in every source, in one query.
+///
+/// Results come back once per distinct term, in the order the terms first
+/// appear. With `with_lns` the LNS keys come back in the same query and
+/// their entries are read as in `lookup_form`; without it `lns` is left
+/// empty. Enclitics are split off as in `lookup_form`, in one further query
+/// for all the terms that need it; no suggestions are made.
+pub fn lookup_many(
+    terms: &[&str],
+    with_lns: bool,
+    options: &QueryOptions,
+    connection: &PgConnection,
+) -> Result<Vec<(String, LookupResult)>, LatinDbError> {
+    let mut results = run_lookup(terms, with_lns, options, connection)?;
+    resolve_enclitics(&mut results, |stems| {
+        run_lookup(stems, with_lns, options, connection)
+    })?;
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::Case;
+
+    #[test]
+    fn test_form_lookup_sql_covers_every_source() {
+        let sql = form_lookup_sql(true, &QueryOptions::default());
+        for source in VOCAB_SOURCES {
+            assert!(sql.contains(&format!("'{}' AS source", source.name())));
+        }
+        assert!(sql.contains("FROM lns_entry_keys"));
+        assert!(sql.contains("k.simple_key = m.simple_key"));
+        assert_eq!(sql.matches("$1").count(), 1);
+        assert!(sql.contains("latin_fold(form)"));
+        let exact = QueryOptions {
+            exact: true,
+            ..Default::default()
+        };
+        assert!(!form_lookup_sql(false, &exact).contains("lns_entry_keys"));
+        assert!(!form_lookup_sql(false, &exact).contains("latin_fold"));
+    }
+
+    #[test]
+    fn test_form_lookup_sql_filters_vocab_rows() {
+        let options = QueryOptions {
+            case: Some(Case::Ablative),
+            ..Default::default()
+        };
+        let sql = form_lookup_sql(true, &options);
+        assert_eq!(
+            sql.matches("lower(h.analysis) ~").count(),
+            VOCAB_SOURCES.len()
+        );
+    }
+}
@@ -0,0 +1,472 @@
+use serde::Serialize;
+use std::str::FromStr;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Case {
+    Nominative,
+    Genitive,
+    Dative,
+    Accusative,
+    Ablative,
+    Vocative,
+    Locative,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Number {
+    Singular,
+    Plural,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Gender {
+    Masculine,
+    Feminine,
+    Neuter,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Tense {
+    Present,
+    Imperfect,
+    Future,
+    Perfect,
+    Pluperfect,
+    FuturePerfect,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Mood {
+    Indicative,
+    Subjunctive,
+    Imperative,
+    Infinitive,
+    Participle,
+    Gerund,
+    Gerundive,
+    Supine,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Voice {
+    Active,
+    Passive,
+    Deponent,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Person {
+    First,
+    Second,
+    Third,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Degree {
+    Positive,
+    Comparative,
+    Superlative,
+}
+
+/// One morphological reading of a form, parsed from `lewis_short_lemmata.analysis`.
+///
+/// The analysis strings are whitespace-separated abbreviations such as
+/// `noun pl masc acc` or `verb 3rd sg pres ind act`; tokens that are not
+/// recognised are kept in `raw` but otherwise ignored. A form that is
+/// ambiguous for gender (`masc/fem`) lists every gender given.
+#[derive(Serialize, Debug, Clone, PartialEq, Default)]
+pub struct Analysis {
+    pub raw: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub part_of_speech: Option<PartOfSpeech>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub case: Option<Case>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub number: Option<Number>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub gender: Vec<Gender>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tense: Option<Tense>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mood: Option<Mood>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voice: Option<Voice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub person: Option<Person>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub degree: Option<Degree>,
+}
+
+/// The value `token` stands for in a table of spellings.
+fn token_value<T: Copy>(tokens: &[(&str, T)], token: &str) -> Option<T> {
+    tokens
+        .iter()
+        .find(|(spelling, _)| *spelling == token)
+        .map(|(_, value)| *value)
+}
+
+/// A part of speech, as spelled by any of the sources: `noun` in the
+/// lemmata and GCSE list, `N` in Whitaker's Words, `adj`/`ADJ`/`adjective`
+/// and so on.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PartOfSpeech {
+    Noun,
+    Verb,
+    Adjective,
+    Adverb,
+    Pronoun,
+    Conjunction,
+    Preposition,
+    Interjection,
+    Numeral,
+    Particle,
+}
+
+impl PartOfSpeech {
+    pub const TOKENS: &'static [(&'static str, PartOfSpeech)] = &[
+        ("noun", PartOfSpeech::Noun),
+        ("n", PartOfSpeech::Noun),
+        ("subst", PartOfSpeech::Noun),
+        ("substantive", PartOfSpeech::Noun),
+        ("verb", PartOfSpeech::Verb),
+        ("v", PartOfSpeech::Verb),
+        ("adj", PartOfSpeech::Adjective),
+        ("adjective", PartOfSpeech::Adjective),
+        ("adv", PartOfSpeech::Adverb),
+        ("adverb", PartOfSpeech::Adverb),
+        ("pron", PartOfSpeech::Pronoun),
+        ("pronoun", PartOfSpeech::Pronoun),
+        ("conj", PartOfSpeech::Conjunction),
+        ("conjunction", PartOfSpeech::Conjunction),
+        ("prep", PartOfSpeech::Preposition),
+        ("preposition", PartOfSpeech::Preposition),
+        ("interj", PartOfSpeech::Interjection),
+        ("interjection", PartOfSpeech::Interjection),
+        ("exclam", PartOfSpeech::Interjection),
+        ("num", PartOfSpeech::Numeral),
+        ("numeral", PartOfSpeech::Numeral),
+        ("particle", PartOfSpeech::Particle),
+    ];
+
+    /// Read a part of speech in any of the sources' spellings, ignoring
+    /// case and a trailing full stop.
+    pub fn from_token(token: &str) -> Option<Self> {
+        token_value(
+            Self::TOKENS,
+            &token.trim().trim_end_matches('.').to_lowercase(),
+        )
+    }
+}
+
+impl Case {
+    pub const TOKENS: &'static [(&'static str, Case)] = &[
+        ("nom", Case::Nominative),
+        ("nominative", Case::Nominative),
+        ("gen", Case::Genitive),
+        ("genitive", Case::Genitive),
+        ("dat", Case::Dative),
+        ("dative", Case::Dative),
+        ("acc", Case::Accusative),
+        ("accusative", Case::Accusative),
+        ("abl", Case::Ablative),
+        ("ablative", Case::Ablative),
+        ("voc", Case::Vocative),
+        ("vocative", Case::Vocative),
+        ("loc", Case::Locative),
+        ("locative", Case::Locative),
+    ];
+
+    pub fn from_token(token: &str) -> Option<Self> {
+        token_value(Self::TOKENS, token)
+    }
+}
+
+impl Number {
+    pub const TOKENS: &'static [(&'static str, Number)] = &[
+        ("sg", Number::Singular),
+        ("singular", Number::Singular),
+        ("pl", Number::Plural),
+        ("plural", Number::Plural),
+    ];
+
+    pub fn from_token(token: &str) -> Option<Self> {
+        token_value(Self::TOKENS, token)
+    }
+}
+
+impl Gender {
+    pub const TOKENS: &'static [(&'static str, Gender)] = &[
+        ("masc", Gender::Masculine),
+        ("masculine", Gender::Masculine),
+        ("fem", Gender::Feminine),
+        ("feminine", Gender::Feminine),
+        ("neut", Gender::Neuter),
+        ("neuter", Gender::Neuter),
+    ];
+
+    pub fn from_token(token: &str) -> Option<Self> {
+        token_value(Self::TOKENS, token)
+    }
+}
+
+impl Tense {
+    pub const TOKENS: &'static [(&'static str, Tense)] = &[
+        ("pres", Tense::Present),
+        ("present", Tense::Present),
+        ("imperf", Tense::Imperfect),
+        ("imperfect", Tense::Imperfect),
+        ("fut", Tense::Future),
+        ("future", Tense::Future),
+        ("perf", Tense::Perfect),
+        ("perfect", Tense::Perfect),
+        ("plupf", Tense::Pluperfect),
+        ("pluperf", Tense::Pluperfect),
+        ("pluperfect", Tense::Pluperfect),
+        ("futperf", Tense::FuturePerfect),
+        ("fut_perf", Tense::FuturePerfect),
+        ("futureperfect", Tense::FuturePerfect),
+    ];
+
+    pub fn from_token(token: &str) -> Option<Self> {
+        token_value(Self::TOKENS, token)
+    }
+}
+
+impl Mood {
+    pub const TOKENS: &'static [(&'static str, Mood)] = &[
+        ("ind", Mood::Indicative),
+        ("indicative", Mood::Indicative),
+        ("subj", Mood::Subjunctive),
+        ("subjunctive", Mood::Subjunctive),
+        ("imperat", Mood::Imperative),
+        ("imperative", Mood::Imperative),
+        ("inf", Mood::Infinitive),
+        ("infinitive", Mood::Infinitive),
+        ("part", Mood::Participle),
+        ("pple", Mood::Participle),
+        ("participle", Mood::Participle),
+        ("gerund", Mood::Gerund),
+        ("gerundive", Mood::Gerundive),
+        ("supine", Mood::Supine),
+    ];
+
+    pub fn from_token(token: &str) -> Option<Self> {
+        token_value(Self::TOKENS, token)
+    }
+}
+
+impl Voice {
+    pub const TOKENS: &'static [(&'static str, Voice)] = &[
+        ("act", Voice::Active),
+        ("active", Voice::Active),
+        ("pass", Voice::Passive),
+        ("passive", Voice::Passive),
+        ("mp", Voice::Passive),
+        ("dep", Voice::Deponent),
+        ("deponent", Voice::Deponent),
+    ];
+
+    pub fn from_token(token: &str) -> Option<Self> {
+        token_value(Self::TOKENS, token)
+    }
+}
+
+impl Person {
+    pub const TOKENS: &'static [(&'static str, Person)] = &[
+        ("1st", Person::First),
+        ("first", Person::First),
+        ("2nd", Person::Second),
+        ("second", Person::Second),
+        ("3rd", Person::Third),
+        ("third", Person::Third),
+    ];
+
+    pub fn from_token(token: &str) -> Option<Self> {
+        token_value(Self::TOKENS, token)
+    }
+}
+
+impl Degree {
+    pub const TOKENS: &'static [(&'static str, Degree)] = &[
+        ("pos", Degree::Positive),
+        ("positive", Degree::Positive),
+        ("comp", Degree::Comparative),
+        ("comparative", Degree::Comparative),
+        ("superl", Degree::Superlative),
+        ("superlative", Degree::Superlative),
+    ];
+
+    pub fn from_token(token: &str) -> Option<Self> {
+        token_value(Self::TOKENS, token)
+    }
+}
+
+/// `FromStr` for the feature enums, so they can be used as CLI arguments.
+macro_rules! from_str_via_token {
+    ($($feature:ident),*) => {
+        $(
+            impl FromStr for $feature {
+                type Err = String;
+
+                fn from_str(s: &str) -> Result<Self, Self::Err> {
+                    $feature::from_token(&s.to_lowercase()).ok_or_else(|| {
+                        format!("unknown {}: {:?}", stringify!($feature).to_lowercase(), s)
+                    })
+                }
+            }
+        )*
+    };
+}
+
+from_str_via_token!(
+    PartOfSpeech,
+    Case,
+    Number,
+    Gender,
+    Tense,
+    Mood,
+    Voice,
+    Person,
+    Degree
+);
+
+/// The analysis tokens that name a part of speech. `part` is deliberately
+/// absent: in the analyses it always means participle.
+pub(crate) const PARTS_OF_SPEECH: [&str; 11] = [
+    "noun", "verb", "adj", "adv", "pron", "conj", "prep", "interj", "numeral", "exclam", "particle",
+];
+
+/// The moods whose readings are verb forms even when the analysis names no
+/// part of speech.
+pub(crate) const VERBAL_NOUN_MOODS: [Mood; 4] = [
+    Mood::Participle,
+    Mood::Gerund,
+    Mood::Gerundive,
+    Mood::Supine,
+];
+
+impl Analysis {
+    pub fn parse(raw: &str) -> Self {
+        let mut analysis = Analysis {
+            raw: raw.to_string(),
+            ..Default::default()
+        };
+        let tokens = raw
+            .split(|c: char| c.is_whitespace() || matches!(c, '/' | ',' | '.' | '(' | ')'))
+            .filter(|token| !token.is_empty());
+        for token in tokens {
+            let token = token.to_lowercase();
+            let token = token.as_str();
+            let part_of_speech = PARTS_OF_SPEECH
+                .contains(&token)
+                .then(|| PartOfSpeech::from_token(token))
+                .flatten();
+            if let Some(part_of_speech) = part_of_speech {
+                analysis.part_of_speech.get_or_insert(part_of_speech);
+            } else if let Some(case) = Case::from_token(token) {
+                analysis.case = Some(case);
+            } else if let Some(number) = Number::from_token(token) {
+                analysis.number = Some(number);
+            } else if let Some(gender) = Gender::from_token(token) {
+                analysis.gender.push(gender);
+            } else if let Some(tense) = Tense::from_token(token) {
+                analysis.tense = Some(tense);
+            } else if let Some(mood) = Mood::from_token(token) {
+                analysis.mood = Some(mood);
+            } else if let Some(voice) = Voice::from_token(token) {
+                analysis.voice = Some(voice);
+            } else if let Some(person) = Person::from_token(token) {
+                analysis.person = Some(person);
+            } else if let Some(degree) = Degree::from_token(token) {
+                analysis.degree = Some(degree);
+            }
+        }
+        // Participles and the like are given no part of speech of their own.
+        if analysis.part_of_speech.is_none()
+            && analysis
+                .mood
+                .is_some_and(|mood| VERBAL_NOUN_MOODS.contains(&mood))
+        {
+            analysis.part_of_speech = Some(PartOfSpeech::Verb);
+        }
+        analysis
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_noun() {
+        let analysis = Analysis::parse("noun pl masc acc");
+        assert_eq!(analysis.part_of_speech, Some(PartOfSpeech::Noun));
+        assert_eq!(analysis.case, Some(Case::Accusative));
+        assert_eq!(analysis.number, Some(Number::Plural));
+        assert_eq!(analysis.gender, vec![Gender::Masculine]);
+        assert_eq!(analysis.tense, None);
+    }
+
+    #[test]
+    fn test_parse_verb() {
+        let analysis = Analysis::parse("verb 3rd sg pres ind act");
+        assert_eq!(analysis.part_of_speech, Some(PartOfSpeech::Verb));
+        assert_eq!(analysis.person, Some(Person::Third));
+        assert_eq!(analysis.number, Some(Number::Singular));
+        assert_eq!(analysis.tense, Some(Tense::Present));
+        assert_eq!(analysis.mood, Some(Mood::Indicative));
+        assert_eq!(analysis.voice, Some(Voice::Active));
+        assert_eq!(analysis.case, None);
+    }
+
+    #[test]
+    fn test_parse_abbreviated_and_ambiguous() {
+        let analysis = Analysis::parse("Acc. Pl. masc/fem");
+        assert_eq!(analysis.case, Some(Case::Accusative));
+        assert_eq!(analysis.number, Some(Number::Plural));
+        assert_eq!(analysis.gender, vec![Gender::Masculine, Gender::Feminine]);
+        assert_eq!(analysis.raw, "Acc. Pl. masc/fem");
+    }
+
+    #[test]
+    fn test_part_of_speech_spellings() {
+        assert_eq!(PartOfSpeech::from_token("N"), Some(PartOfSpeech::Noun));
+        assert_eq!(
+            PartOfSpeech::from_token("ADJ"),
+            Some(PartOfSpeech::Adjective)
+        );
+        assert_eq!(
+            PartOfSpeech::from_token("conjunction"),
+            Some(PartOfSpeech::Conjunction)
+        );
+        assert_eq!(
+            PartOfSpeech::from_token("particle"),
+            Some(PartOfSpeech::Particle)
+        );
+        assert_eq!("Abl".parse::<Case>(), Ok(Case::Ablative));
+        assert!("ablatival".parse::<Case>().is_err());
+    }
+
+    #[test]
+    fn test_participle_is_a_verb() {
+        let analysis = Analysis::parse("part perf pass masc nom sg");
+        assert_eq!(analysis.mood, Some(Mood::Participle));
+        assert_eq!(analysis.part_of_speech, Some(PartOfSpeech::Verb));
+    }
+
+    #[test]
+    fn test_every_part_of_speech_token_is_recognised() {
+        for token in PARTS_OF_SPEECH {
+            assert!(PartOfSpeech::from_token(token).is_some(), "{}", token);
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use crate::error::LatinDbError;
+use crate::models::VocabHit;
+use crate::sources::{list_rows, VocabSource};
+use diesel::pg::PgConnection;
+
+/// The file headers Anki reads before the notes: tab-separated HTML fields,
+/// with the note GUID in the first column and the tags in the last.
+const HEADER: &str =
+    "#separator:tab\n#html:true\n#notetype:Basic\n#guid column:1\n#tags column:4\n";
+
+/// The note GUID for a row: the list name and the row's `id`, so the same
+/// row always gets the same GUID and re-importing updates its card.
+pub fn note_guid(list: &str, id: i64) -> String {
+    format!("latin-db-{}-{}", list, id)
+}
+
+/// Escape a field for an HTML Anki import. Tabs and line breaks would end
+/// the field, so they become spaces.
+fn field(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace(['\t', '\n', '\r'], " ")
+}
+
+/// A tag can not contain spaces, so `1st conj` becomes `1st_conj`.
+fn tag(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+/// One import line: GUID, front (the dictionary form), back (the meaning
+/// with the part of speech under it) and tags (the list and the part of
+/// speech).
+fn note(list: &str, hit: &VocabHit) -> String {
+    let mut back = field(&hit.meaning);
+    let mut tags = vec![tag(list)];
+    if let Some(part_of_speech) = hit.part_of_speech.as_deref().filter(|p| !p.is_empty()) {
+        back.push_str(&format!("<br><i>{}</i>", field(part_of_speech)));
+        tags.push(tag(part_of_speech));
+    }
+    format!(
+        "{}\t{}\t{}\t{}\n",
+        note_guid(list, hit.id),
+        field(&hit.dict_form),
+        back,
+        tags.join(" ")
+    )
+}
+
+/// Render rows of the list `list` as an Anki import file.
+pub fn render_notes(list: &str, hits: &[VocabHit]) -> String {
+    let mut out = HEADER.to_string();
+    for hit in hits {
+        out.push_str(&note(list, hit));
+    }
+    out
+}
+
+/// Export whole lists as one Anki import file, with the notes of each list
+/// tagged with its name.
+pub fn export_lists(
+    sources: &[&dyn VocabSource],
+    connection: &PgConnection,
+) -> Result<String, LatinDbError> {
+    let mut out = HEADER.to_string();
+    for source in sources {
+        for hit in list_rows(*source, connection)? {
+            out.push_str(&note(source.name(), &hit));
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(id: i64, dict_form: &str, part_of_speech: Option<&str>, meaning: &str) -> VocabHit {
+        VocabHit {
+            id,
+            headword: "headword".to_string(),
+            dict_form: dict_form.to_string(),
+            part_of_speech: part_of_speech.map(str::to_string),
+            class: None,
+            meaning: meaning.to_string(),
+            analyses: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_render_notes() {
+        let out = render_notes(
+            "gcse",
+            &[
+                hit(3, "servus, servi, m.", Some("noun"), "slave"),
+                hit(
+                    7,
+                    "amo, amare",
+                    Some("1st conj verb"),
+                    "love <romantic>\tlike",
+                ),
+                hit(9, "ecce", None, "look!"),
+            ],
+        );
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "#separator:tab");
+        assert_eq!(
+            lines[5],
+            "latin-db-gcse-3\tservus, servi, m.\tslave<br><i>noun</i>\tgcse noun"
+        );
+        assert_eq!(
+            lines[6],
+            "latin-db-gcse-7\tamo, amare\tlove &lt;romantic&gt; like<br><i>1st conj verb</i>\tgcse 1st_conj_verb"
+        );
+        assert_eq!(lines[7], "latin-db-gcse-9\tecce\tlook!\tgcse");
+    }
+
+    #[test]
+    fn test_note_guid_is_stable_per_list() {
+        assert_eq!(note_guid("gcse", 3), note_guid("gcse", 3));
+        assert_ne!(note_guid("gcse", 3), note_guid("asvocab", 3));
+    }
+}
@@ -0,0 +1,37 @@
+use clap::Parser;
+use latin_dictionary::anki::export_lists;
+use latin_dictionary::{vocab_source, VocabSource, VOCAB_SOURCES};
+use std::process;
+
+/// Write vocabulary lists as a tab-separated Anki import file
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// The lists to export, e.g. gcse asvocab
+    #[clap(required = true)]
+    lists: Vec<String>,
+}
+
+fn main() {
+    let args = Args::parse();
+    let sources: Vec<&dyn VocabSource> = args
+        .lists
+        .iter()
+        .map(|name| {
+            vocab_source(name).unwrap_or_else(|| {
+                let names: Vec<&str> = VOCAB_SOURCES.iter().map(|s| s.name()).collect();
+                eprintln!("Unknown list: {}; choose from {}", name, names.join(", "));
+                process::exit(1);
+            })
+        })
+        .collect();
+    run_query(&sources);
+}
+
+fn run_query(sources: &[&dyn VocabSource]) {
+    let pool = latin_dictionary::get_connection_pool();
+    let conn = pool.get().expect("Could not get connection from pool");
+    let export = export_lists(sources, &conn).expect("Database did not return result.");
+
+    print!("{}", export);
+}
@@ -0,0 +1,34 @@
+use clap::Parser;
+use latin_dictionary::config::LnsConfig;
+use latin_dictionary::lns_index::{LnsIndex, INDEX_FILE};
+use std::path::PathBuf;
+use std::process;
+
+/// Index the Lewis & Short XML files so lookups can seek straight to an
+/// entry. Rerun after the XML files change.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Directory holding the Lewis & Short XML files [default: $LATIN_LNS_DIR]
+    #[clap(long)]
+    lns_dir: Option<PathBuf>,
+}
+
+fn main() {
+    let args = Args::parse();
+    let lns = args
+        .lns_dir
+        .map_or_else(LnsConfig::from_env, |dir| Ok(LnsConfig::new(dir)));
+    let lns = match lns.and_then(|lns| lns.validate().map(|_| lns)) {
+        Ok(lns) => lns,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+    let dir = &lns.data_dir;
+    let index = LnsIndex::build(dir).expect("Could not index the LNS files");
+    let path = dir.join(INDEX_FILE);
+    index.write(&path).expect("Could not write the LNS index");
+    println!("Indexed {} entries in {}", index.len(), path.display());
+}
@@ -0,0 +1,37 @@
+use clap::Parser;
+use latin_dictionary::coverage::coverage;
+use latin_dictionary::tokenize::tokenize;
+use std::fs;
+use std::io::{self, Read};
+
+/// Report how much of a Latin passage is on the GCSE, AS and CLC lists
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// A file containing the passage; read from stdin if omitted
+    file: Option<String>,
+}
+
+fn main() {
+    let args = Args::parse();
+    let passage = match args.file {
+        Some(path) => fs::read_to_string(path).expect("Could not read passage"),
+        None => {
+            let mut passage = String::new();
+            io::stdin()
+                .read_to_string(&mut passage)
+                .expect("Could not read passage");
+            passage
+        }
+    };
+    run_query(&passage);
+}
+
+fn run_query(passage: &str) {
+    let pool = latin_dictionary::get_connection_pool();
+    let conn = pool.get().expect("Could not get connection from pool");
+    let report = coverage(&tokenize(passage), &conn).expect("Database did not return result.");
+
+    let res_str = serde_json::to_string(&report).expect("Could not serialize result");
+    println!("{}", res_str);
+}
@@ -0,0 +1,65 @@
+use clap::Parser;
+use latin_dictionary::configure_lns_for_lookups;
+use latin_dictionary::glossary::{glossary, render, GlossFormat};
+use latin_dictionary::tokenize::tokenize;
+use latin_dictionary::LatinDbError;
+use latin_dictionary::LnsConfig;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+/// Print a running vocabulary for a Latin passage, in order of first appearance
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// A file containing the passage
+    file: String,
+    /// markdown, csv or latex
+    #[clap(long, default_value = "markdown")]
+    format: GlossFormat,
+    /// Leave out words already on this list: gcse, asvocab, clc or wwords
+    #[clap(long)]
+    omit: Option<String>,
+    /// Directory holding the Lewis & Short XML files [default: $LATIN_LNS_DIR]
+    #[clap(long)]
+    lns_dir: Option<PathBuf>,
+}
+
+fn main() {
+    let args = Args::parse();
+    let lns = args
+        .lns_dir
+        .map_or_else(LnsConfig::from_env, |dir| Ok(LnsConfig::new(dir)));
+    let passage = fs::read_to_string(&args.file).expect("Could not read passage");
+    run_query(&passage, args.format, args.omit.as_deref(), lns);
+}
+
+fn run_query(
+    passage: &str,
+    format: GlossFormat,
+    omit: Option<&str>,
+    lns: Result<LnsConfig, LatinDbError>,
+) {
+    let pool = latin_dictionary::get_connection_pool();
+    let conn = pool.get().expect("Could not get connection from pool");
+    match configure_lns_for_lookups(lns, &conn) {
+        Ok(None) => {}
+        Ok(Some(warning)) => eprintln!(
+            "Warning: {}; only LNS entries imported into the database can be read",
+            warning
+        ),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+    let entries = match glossary(&tokenize(passage), omit, &conn) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+
+    print!("{}", render(&entries, format));
+}
@@ -0,0 +1,44 @@
+use clap::Parser;
+use latin_dictionary::config::LnsConfig;
+use latin_dictionary::lns_store::import_lns;
+use std::path::PathBuf;
+use std::process;
+
+/// Parse every entry in the Lewis & Short XML files and store it in the
+/// lns_entries table. Rerun after the XML files change.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Directory holding the Lewis & Short XML files [default: $LATIN_LNS_DIR]
+    #[clap(long)]
+    lns_dir: Option<PathBuf>,
+}
+
+fn main() {
+    let args = Args::parse();
+    let lns = args
+        .lns_dir
+        .map_or_else(LnsConfig::from_env, |dir| Ok(LnsConfig::new(dir)));
+    let lns = match lns.and_then(|lns| lns.validate().map(|_| lns)) {
+        Ok(lns) => lns,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+    let pool = latin_dictionary::get_connection_pool();
+    let conn = pool.get().expect("Could not get connection from pool");
+    let summary = import_lns(&lns.data_dir, &conn).expect("Could not import the LNS files");
+    println!(
+        "Imported {} entries from {}",
+        summary.imported,
+        lns.data_dir.display()
+    );
+    if !summary.skipped.is_empty() {
+        println!(
+            "Skipped {} that could not be parsed: {}",
+            summary.skipped.len(),
+            summary.skipped.join(", ")
+        );
+    }
+}
@@ -1,21 +1,73 @@
 use clap::Parser;
-use latin_dictionary::get_lns_key;
-use latin_dictionary::query_asvocab;
-use latin_dictionary::query_clc4;
-use latin_dictionary::query_gcse_latin;
-use latin_dictionary::query_wwords;
-use latin_dictionary::QueryFunc;
+use latin_dictionary::analysis::{
+    Case, Degree, Gender, Mood, Number, PartOfSpeech, Person, Tense, Voice,
+};
+use latin_dictionary::configure_lns_for_lookups;
+use latin_dictionary::form_membership;
+use latin_dictionary::lookup_form;
+use latin_dictionary::resolve_form_threaded;
+use latin_dictionary::suggest;
+use latin_dictionary::try_get_connection_pool;
+use latin_dictionary::LatinDbError;
+use latin_dictionary::ListMembership;
+use latin_dictionary::LnsConfig;
+use latin_dictionary::LookupResult;
+use latin_dictionary::PoolConfig;
+use latin_dictionary::QueryOptions;
+use latin_dictionary::Suggestion;
+use serde::Serialize;
 // use std::env;
-use std::sync::Arc;
-use std::thread;
-
+use std::path::PathBuf;
+use std::process;
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
     term: String,
+    /// Query each source on its own thread and connection instead of in one round trip
+    #[clap(long)]
+    threaded: bool,
+    /// When nothing matches exactly, list forms and headwords starting with the term
+    #[clap(long)]
+    suggest: bool,
+    /// Only show readings with this part of speech, e.g. verb
+    #[clap(long = "pos")]
+    part_of_speech: Option<PartOfSpeech>,
+    /// Only show readings in this case, e.g. abl
+    #[clap(long)]
+    case: Option<Case>,
+    #[clap(long)]
+    number: Option<Number>,
+    #[clap(long)]
+    gender: Option<Gender>,
+    #[clap(long)]
+    tense: Option<Tense>,
+    #[clap(long)]
+    mood: Option<Mood>,
+    #[clap(long)]
+    voice: Option<Voice>,
+    #[clap(long)]
+    person: Option<Person>,
+    #[clap(long)]
+    degree: Option<Degree>,
+    /// Match the term exactly as typed, without normalizing case, u/v, i/j or macrons
+    #[clap(long)]
+    exact: bool,
+    /// Directory holding the Lewis & Short XML files [default: $LATIN_LNS_DIR]
+    #[clap(long)]
+    lns_dir: Option<PathBuf>,
 }
 
+/// The lookup result, plus prefix completions when asked for and nothing matched.
+#[derive(Serialize)]
+struct Output<'a> {
+    #[serde(flatten)]
+    result: &'a LookupResult,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    completions: Option<Vec<Suggestion>>,
+    /// Which lists each headword the term resolves to is on.
+    membership: Vec<ListMembership>,
+}
 
 fn main() {
     // let args: Vec<String> = env::args().collect();
@@ -25,31 +77,64 @@ fn main() {
     //     None => eprintln!("No search term provided!"),
     // }
     let args = Args::parse();
-    run_query(Arc::new(args.term));
+    let lns = args
+        .lns_dir
+        .map_or_else(LnsConfig::from_env, |dir| Ok(LnsConfig::new(dir)));
+    let options = QueryOptions {
+        part_of_speech: args.part_of_speech,
+        case: args.case,
+        number: args.number,
+        gender: args.gender,
+        tense: args.tense,
+        mood: args.mood,
+        voice: args.voice,
+        person: args.person,
+        degree: args.degree,
+        exact: args.exact,
+    };
+    if let Err(e) = run_query(&args.term, &options, args.threaded, args.suggest, lns) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
 }
-fn run_query(term: Arc<String>) {
-    let pool = latin_dictionary::get_connection_pool();
-    let mut threads = vec![];
-    let queries: [QueryFunc; 5] = [query_gcse_latin, query_clc4, query_asvocab, query_wwords, get_lns_key];
-
-    for f in queries {
-        let pool1 = pool.clone();
-        let term1 = term.clone();
-        threads.push(thread::spawn({
-            move || {
-                let conn = &mut pool1.get().expect("Could not get connection from pool");
-                f(&term1, conn).expect("Database did not return result.")
-            }
-        }))
+fn run_query(
+    term: &str,
+    options: &QueryOptions,
+    threaded: bool,
+    with_suggestions: bool,
+    lns: Result<LnsConfig, LatinDbError>,
+) -> Result<(), LatinDbError> {
+    let pool = try_get_connection_pool(&PoolConfig::from_env()?)?;
+    let conn = pool.get()?;
+    if let Some(warning) = configure_lns_for_lookups(lns, &conn)? {
+        eprintln!(
+            "Warning: {}; only LNS entries imported into the database can be read",
+            warning
+        );
     }
+    let result = if threaded {
+        resolve_form_threaded(term, options, &pool)?
+    } else {
+        lookup_form(term, options, &conn)?
+    };
 
-    let mut results: Vec<String> = Vec::new();
-    for handle in threads {
-        let res = handle.join().unwrap();
-        results.push(res);
+    let mut completions = None;
+    if with_suggestions && result.is_empty() {
+        completions = Some(suggest(term, 10, 0, &conn)?);
     }
 
-    let res_str = results.join(", ");
-    println!("{{{}}}", res_str);
+    let form = result
+        .compound
+        .as_ref()
+        .map_or(term, |compound| compound.stem.as_str());
+    let membership = form_membership(form, options.exact, &conn)?;
 
+    let output = Output {
+        result: &result,
+        completions,
+        membership,
+    };
+    let res_str = serde_json::to_string(&output)?;
+    println!("{}", res_str);
+    Ok(())
 }
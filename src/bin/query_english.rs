@@ -0,0 +1,26 @@
+use clap::Parser;
+use latin_dictionary::reverse_lookup;
+
+/// Find the Latin words whose meaning matches some English
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    english: String,
+    /// Show at most this many headwords
+    #[clap(long)]
+    limit: Option<usize>,
+}
+
+fn main() {
+    let args = Args::parse();
+    run_query(&args.english, args.limit);
+}
+
+fn run_query(english: &str, limit: Option<usize>) {
+    let pool = latin_dictionary::get_connection_pool();
+    let conn = pool.get().expect("Could not get connection from pool");
+    let hits = reverse_lookup(english, limit, &conn).expect("Database did not return result.");
+
+    let res_str = serde_json::to_string(&hits).expect("Could not serialize result");
+    println!("{}", res_str);
+}
@@ -1,79 +1,51 @@
 use clap::Parser;
-use latin_dictionary::get_lns_key_headword;
-use latin_dictionary::query_asvocab_headword;
-use latin_dictionary::query_clc4_headword;
-use latin_dictionary::query_gcse_latin_headword;
-use latin_dictionary::query_wwords_headword;
+use latin_dictionary::configure_lns_for_lookups;
+use latin_dictionary::lookup_headword_threaded;
+use latin_dictionary::LatinDbError;
+use latin_dictionary::LnsConfig;
+use latin_dictionary::LookupResult;
 // use std::env;
-use std::sync::Arc;
-use std::thread;
+use std::path::PathBuf;
+use std::process;
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
     term: String,
+    /// Match the headword exactly as typed, without normalizing its spelling
+    #[clap(long)]
+    exact: bool,
+    /// Directory holding the Lewis & Short XML files [default: $LATIN_LNS_DIR]
+    #[clap(long)]
+    lns_dir: Option<PathBuf>,
 }
 
 fn main() {
     let args = Args::parse();
-    run_query(Arc::new(args.term));
+    let lns = args
+        .lns_dir
+        .map_or_else(LnsConfig::from_env, |dir| Ok(LnsConfig::new(dir)));
+    run_query(&args.term, args.exact, lns);
 }
 
-fn run_query(term: Arc<String>) {
+fn run_query(term: &str, exact: bool, lns: Result<LnsConfig, LatinDbError>) {
     let pool = latin_dictionary::get_connection_pool();
-    let mut threads = vec![];
-
-    let pool0 = pool.clone();
-    let term0 = term.clone();
-    threads.push(thread::spawn({
-        move || {
-            let conn = &mut pool0.get().unwrap();
-            get_lns_key_headword(&term0, conn).unwrap()
-        }
-    }));
-
-    let pool1 = pool.clone();
-    let term1 = term.clone();
-    threads.push(thread::spawn({
-        move || {
-            let conn = &mut pool1.get().unwrap();
-            query_gcse_latin_headword(&term1, conn).unwrap()
-        }
-    }));
-
-    let pool2 = pool.clone();
-    let term2 = term.clone();
-    threads.push(thread::spawn({
-        move || {
-            let conn = &mut pool2.get().unwrap();
-            query_clc4_headword(&term2, conn).unwrap()
+    let conn = pool.get().expect("Could not get connection from pool");
+    let configured = configure_lns_for_lookups(lns, &conn);
+    drop(conn);
+    match configured {
+        Ok(None) => {}
+        Ok(Some(warning)) => eprintln!(
+            "Warning: {}; only LNS entries imported into the database can be read",
+            warning
+        ),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
         }
-    }));
-
-    let pool3 = pool.clone();
-    let term3 = term.clone();
-    threads.push(thread::spawn({
-        move || {
-            let conn = &mut pool3.get().unwrap();
-            query_asvocab_headword(&term3, conn).unwrap()
-        }
-    }));
-
-    let pool4 = pool.clone();
-    let term4 = term.clone();
-    threads.push(thread::spawn({
-        move || {
-            let conn = &mut pool4.get().unwrap();
-            query_wwords_headword(&term4, conn).unwrap()
-        }
-    }));
-
-    let mut results: Vec<String> = Vec::new();
-    for handle in threads {
-        let res = handle.join().unwrap();
-        results.push(res);
     }
+    let result = LookupResult::from(lookup_headword_threaded(term, exact, &pool));
 
-    let res_str = results.join(", ");
-    println!("{{{}}}", res_str);
+    let res_str = serde_json::to_string(&result).unwrap();
+    println!("{}", res_str);
 }
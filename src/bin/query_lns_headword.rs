@@ -1,23 +1,49 @@
 use clap::Parser;
+use latin_dictionary::configure_lns_for_lookups;
 use latin_dictionary::get_lns_key_headword;
+use latin_dictionary::LatinDbError;
+use latin_dictionary::LnsConfig;
+use std::path::PathBuf;
+use std::process;
 use std::sync::Arc;
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
     term: String,
+    /// Match the headword exactly as typed, without normalizing its spelling
+    #[clap(long)]
+    exact: bool,
+    /// Directory holding the Lewis & Short XML files [default: $LATIN_LNS_DIR]
+    #[clap(long)]
+    lns_dir: Option<PathBuf>,
 }
 
 fn main() {
     let args = Args::parse();
-    run_query(Arc::new(args.term));
+    let lns = args
+        .lns_dir
+        .map_or_else(LnsConfig::from_env, |dir| Ok(LnsConfig::new(dir)));
+    run_query(Arc::new(args.term), args.exact, lns);
 }
 
-fn run_query(term: Arc<String>) {
+fn run_query(term: Arc<String>, exact: bool, lns: Result<LnsConfig, LatinDbError>) {
     let pool = latin_dictionary::get_connection_pool();
 
     let conn = &mut pool.get().unwrap();
-    let res_str = get_lns_key_headword(&term, conn).unwrap();
+    match configure_lns_for_lookups(lns, conn) {
+        Ok(None) => {}
+        Ok(Some(warning)) => eprintln!(
+            "Warning: {}; only LNS entries imported into the database can be read",
+            warning
+        ),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+    let entries = get_lns_key_headword(&term, exact, conn).unwrap();
 
-    println!("{{{}}}", res_str);
+    let res_str = serde_json::to_string(&entries).unwrap();
+    println!("{{\"lns\": {}}}", res_str);
 }
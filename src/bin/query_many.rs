@@ -1,16 +1,18 @@
-use clap::Parser;
 use clap::ArgGroup;
-// use latin_dictionary::get_lns_key;
-use latin_dictionary::query_asvocab;
-// use latin_dictionary::query_clc4;
-use latin_dictionary::query_gcse_latin;
-// use latin_dictionary::query_wwords;
-use latin_dictionary::QueryFunc;
-// use std::env;
-// use std::sync::Arc;
-use std::thread;
-
+use clap::Parser;
+use latin_dictionary::configure_lns_for_lookups;
+use latin_dictionary::lns_cache::parse_frequency_list;
+use latin_dictionary::lns_cache_stats;
+use latin_dictionary::lookup_many;
+use latin_dictionary::warm_lns_cache;
+use latin_dictionary::LatinDbError;
+use latin_dictionary::LnsConfig;
+use latin_dictionary::QueryOptions;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
 
+/// Print the GCSE or AS hits for each term, and with --lns its Lewis & Short entries
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 #[clap(group(
@@ -24,43 +26,101 @@ struct Args {
     gcse: bool,
     #[clap(long)]
     asvocab: bool,
+    /// Match each term exactly as typed, without normalizing its spelling
+    #[clap(long)]
+    exact: bool,
+    /// Print each term as {"<list>": hits, "lns": entries} instead of just its hits
+    #[clap(long)]
+    lns: bool,
+    /// Directory holding the Lewis & Short XML files [default: $LATIN_LNS_DIR]
+    #[clap(long)]
+    lns_dir: Option<PathBuf>,
+    /// Warm the LNS cache from this frequency list first, and report its hits and misses on stderr
+    #[clap(long, requires = "lns")]
+    warm: Option<PathBuf>,
 }
 
 fn main() {
     let args = Args::parse();
     // println!{"{:#?}", args};
-    let f: QueryFunc = match args.gcse {
-        true => query_gcse_latin,
-        false => query_asvocab,
+    let name = match args.gcse {
+        true => "gcse",
+        false => "asvocab",
     };
-    run_query(args.terms, f);
+    let lns = args.lns.then(|| {
+        args.lns_dir
+            .map_or_else(LnsConfig::from_env, |dir| Ok(LnsConfig::new(dir)))
+    });
+    let warm = args.warm.map(|path| match fs::read_to_string(&path) {
+        Ok(text) => parse_frequency_list(&text),
+        Err(e) => {
+            eprintln!("Could not read {}: {}", path.display(), e);
+            process::exit(1);
+        }
+    });
+    run_query(args.terms, name, args.exact, lns, warm);
 }
 
-fn run_query(terms: Vec<String>, f: QueryFunc) {
+fn run_query(
+    terms: Vec<String>,
+    name: &str,
+    exact: bool,
+    lns: Option<Result<LnsConfig, LatinDbError>>,
+    warm: Option<Vec<String>>,
+) {
     let pool = latin_dictionary::get_connection_pool();
-    let mut threads = vec![];
-
-    for term in terms {
-        let pool1 = pool.clone();
-        let term1 = term.clone();
-        threads.push(thread::spawn({
-            move || {
-                let conn = &mut pool1.get().expect("Could not get connection from pool");
-                let res = f(&term1, conn).expect("Database did not return result.");
-                (term1, res)
+    let conn = pool.get().expect("Could not get connection from pool");
+    let with_lns = lns.is_some();
+    if let Some(lns) = lns {
+        match configure_lns_for_lookups(lns, &conn) {
+            Ok(None) => {}
+            Ok(Some(warning)) => eprintln!(
+                "Warning: {}; only LNS entries imported into the database can be read",
+                warning
+            ),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
             }
-        }))
+        }
     }
+    if let Some(words) = &warm {
+        let cached = warm_lns_cache(words, &conn).expect("Could not warm the LNS cache");
+        eprintln!("Warmed the LNS cache with {} entries", cached);
+    }
+    let terms: Vec<&str> = terms.iter().map(String::as_str).collect();
+    let lookups = lookup_many(
+        &terms,
+        with_lns,
+        &QueryOptions {
+            exact,
+            ..Default::default()
+        },
+        &conn,
+    )
+    .expect("Database did not return result.");
 
     let mut results: Vec<String> = Vec::new();
-    for handle in threads {
-        let (term, entry) = handle.join().unwrap();
-        let (_, tail) = entry.split_once(": ").expect("Data returned from query was malformed");
-        let res = format!("\"{term}\": {tail}");
+    for (term, result) in lookups {
+        let key = serde_json::to_string(&term).expect("Could not serialize term");
+        let mut json =
+            serde_json::to_string(result.source(name)).expect("Could not serialize result");
+        if with_lns {
+            let lns = serde_json::to_string(&result.lns).expect("Could not serialize result");
+            json = format!("{{\"{name}\": {json}, \"lns\": {lns}}}");
+        }
+        let res = format!("{key}: {json}");
         results.push(res);
     }
 
     let res_str = results.join(", ");
     println!("{{{}}}", res_str);
 
+    if warm.is_some() {
+        let stats = lns_cache_stats();
+        eprintln!(
+            "LNS cache: {} hits, {} misses, {} of {} entries",
+            stats.hits, stats.misses, stats.len, stats.capacity
+        );
+    }
 }
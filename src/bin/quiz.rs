@@ -0,0 +1,74 @@
+use clap::Parser;
+use latin_dictionary::quiz::{quiz, Direction, Question, QuizOptions};
+use latin_dictionary::{vocab_source, VocabSource, VOCAB_SOURCES};
+use serde::Serialize;
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Print a multiple-choice vocabulary quiz as JSON
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// The lists to draw words from; wwords is far larger than the others and slow to quiz on
+    #[clap(default_values = &["gcse"])]
+    lists: Vec<String>,
+    #[clap(long, default_value = "10")]
+    questions: usize,
+    /// Choices per question, including the right one
+    #[clap(long, default_value = "4")]
+    choices: usize,
+    /// latin_to_english or english_to_latin; both are mixed if omitted
+    #[clap(long)]
+    direction: Option<Direction>,
+    /// Reuse the seed of an earlier paper to reproduce it
+    #[clap(long)]
+    seed: Option<u64>,
+}
+
+/// The paper, with the seed that reproduces it.
+#[derive(Serialize)]
+struct Output {
+    seed: u64,
+    questions: Vec<Question>,
+}
+
+fn main() {
+    let args = Args::parse();
+    let sources: Vec<&dyn VocabSource> = args
+        .lists
+        .iter()
+        .map(|name| {
+            vocab_source(name).unwrap_or_else(|| {
+                let names: Vec<&str> = VOCAB_SOURCES.iter().map(|s| s.name()).collect();
+                eprintln!("Unknown list: {}; choose from {}", name, names.join(", "));
+                process::exit(1);
+            })
+        })
+        .collect();
+    let seed = args.seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Clock is before 1970")
+            .as_secs()
+    });
+    let options = QuizOptions {
+        questions: args.questions,
+        choices: args.choices,
+        direction: args.direction,
+        seed,
+    };
+    run_query(&sources, &options);
+}
+
+fn run_query(sources: &[&dyn VocabSource], options: &QuizOptions) {
+    let pool = latin_dictionary::get_connection_pool();
+    let conn = pool.get().expect("Could not get connection from pool");
+    let questions = quiz(sources, options, &conn).expect("Database did not return result.");
+
+    let output = Output {
+        seed: options.seed,
+        questions,
+    };
+    let res_str = serde_json::to_string(&output).expect("Could not serialize result");
+    println!("{}", res_str);
+}
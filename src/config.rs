@@ -0,0 +1,281 @@
+use crate::error::LatinDbError;
+use crate::search::XML_FILES;
+use crate::DbPool;
+use diesel::pg::PgConnection;
+use diesel::r2d2::{ConnectionManager, Pool};
+use std::collections::{BTreeSet, HashMap};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+pub const DATABASE_URL_VAR: &str = "LATIN_PG_DATABASE_URL";
+pub const MAX_SIZE_VAR: &str = "LATIN_PG_POOL_MAX_SIZE";
+pub const MIN_IDLE_VAR: &str = "LATIN_PG_POOL_MIN_IDLE";
+pub const TIMEOUT_VAR: &str = "LATIN_PG_POOL_TIMEOUT_SECS";
+pub const TEST_ON_CHECK_OUT_VAR: &str = "LATIN_PG_POOL_TEST_ON_CHECK_OUT";
+pub const LNS_DIR_VAR: &str = "LATIN_LNS_DIR";
+
+/// Settings for the Postgres connection pool.
+///
+/// Build one in code with `PoolConfig::new(url)` and struct update syntax,
+/// or read it from the `LATIN_PG_*` environment variables or a file of
+/// `KEY=value` lines using the same names.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolConfig {
+    pub url: String,
+    pub max_size: u32,
+    /// Idle connections to keep open; `None` keeps `max_size` of them.
+    pub min_idle: Option<u32>,
+    pub connection_timeout: Duration,
+    pub test_on_check_out: bool,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        PoolConfig {
+            url: String::new(),
+            max_size: 25,
+            min_idle: None,
+            connection_timeout: Duration::from_secs(30),
+            test_on_check_out: true,
+        }
+    }
+}
+
+impl PoolConfig {
+    pub fn new(url: &str) -> Self {
+        PoolConfig {
+            url: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Read the pool settings from the process environment.
+    pub fn from_env() -> Result<Self, LatinDbError> {
+        Self::from_vars(|name| env::var(name).ok())
+    }
+
+    /// Read the pool settings from a file of `KEY=value` lines.
+    ///
+    /// Blank lines and lines starting with `#` are ignored, and values may be
+    /// wrapped in quotes. Unlike `dotenv::from_path`, this leaves the process
+    /// environment alone.
+    pub fn from_file(path: &Path) -> Result<Self, LatinDbError> {
+        let text = fs::read_to_string(path).map_err(|e| {
+            LatinDbError::Config(format!("could not read {}: {}", path.display(), e))
+        })?;
+        let mut vars = HashMap::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (name, value) = line.split_once('=').ok_or_else(|| {
+                LatinDbError::Config(format!("could not parse {}: {:?}", path.display(), line))
+            })?;
+            let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+            vars.insert(name.trim().to_string(), value.to_string());
+        }
+        Self::from_vars(|name| vars.get(name).cloned())
+    }
+
+    /// Check the settings r2d2 would otherwise panic on.
+    pub fn validate(&self) -> Result<(), LatinDbError> {
+        if self.max_size == 0 {
+            return Err(LatinDbError::Config(
+                "max_size must be positive".to_string(),
+            ));
+        }
+        if self
+            .min_idle
+            .is_some_and(|min_idle| min_idle > self.max_size)
+        {
+            return Err(LatinDbError::Config(
+                "min_idle must be no larger than max_size".to_string(),
+            ));
+        }
+        if self.connection_timeout.is_zero() {
+            return Err(LatinDbError::Config(
+                "connection_timeout must be positive".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, LatinDbError> {
+        let url = var(DATABASE_URL_VAR)
+            .ok_or_else(|| LatinDbError::Config(format!("{} must be set", DATABASE_URL_VAR)))?;
+        let defaults = PoolConfig::default();
+        Ok(PoolConfig {
+            url,
+            max_size: parse_var(&var, MAX_SIZE_VAR)?.unwrap_or(defaults.max_size),
+            min_idle: parse_var(&var, MIN_IDLE_VAR)?,
+            connection_timeout: parse_var(&var, TIMEOUT_VAR)?
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.connection_timeout),
+            test_on_check_out: parse_var(&var, TEST_ON_CHECK_OUT_VAR)?
+                .unwrap_or(defaults.test_on_check_out),
+        })
+    }
+}
+
+fn parse_var<T: FromStr>(
+    var: &impl Fn(&str) -> Option<String>,
+    name: &str,
+) -> Result<Option<T>, LatinDbError> {
+    match var(name) {
+        Some(value) => match value.trim().parse() {
+            Ok(parsed) => Ok(Some(parsed)),
+            Err(_) => Err(LatinDbError::Config(format!(
+                "{} has an invalid value: {:?}",
+                name, value
+            ))),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Where the Lewis & Short XML files (and their index) live, and how many
+/// parsed entries to keep in memory.
+///
+/// Install one with `configure_lns`; until then the library uses
+/// `LnsConfig::from_env()`, and LNS lookups fail if that is not set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LnsConfig {
+    pub data_dir: PathBuf,
+    /// Entries the LRU cache holds; 0 turns it off.
+    pub cache_capacity: usize,
+}
+
+impl LnsConfig {
+    pub fn new(data_dir: impl Into<PathBuf>) -> Self {
+        LnsConfig {
+            data_dir: data_dir.into(),
+            cache_capacity: 4096,
+        }
+    }
+
+    /// Read the directory from `LATIN_LNS_DIR`. There is no default: an
+    /// unset or empty variable is an `LnsDirUnset` error.
+    pub fn from_env() -> Result<Self, LatinDbError> {
+        match env::var(LNS_DIR_VAR) {
+            Ok(dir) if !dir.is_empty() => Ok(LnsConfig::new(dir)),
+            _ => Err(LatinDbError::LnsDirUnset),
+        }
+    }
+
+    /// Check that every file named in `XML_FILES` is in the directory.
+    pub fn validate(&self) -> Result<(), LatinDbError> {
+        let files: BTreeSet<&str> = XML_FILES.values().copied().collect();
+        let missing: Vec<String> = files
+            .into_iter()
+            .filter(|file| !self.data_dir.join(file).is_file())
+            .map(str::to_string)
+            .collect();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(LatinDbError::LnsDirInvalid {
+                dir: self.data_dir.clone(),
+                missing,
+            })
+        }
+    }
+}
+
+/// Build a connection pool, returning an error rather than panicking if the
+/// settings are invalid or the database cannot be reached.
+pub fn try_get_connection_pool(config: &PoolConfig) -> Result<DbPool, LatinDbError> {
+    config.validate()?;
+    let manager = ConnectionManager::<PgConnection>::new(config.url.as_str());
+    let pool = Pool::builder()
+        .max_size(config.max_size)
+        .min_idle(config.min_idle)
+        .connection_timeout(config.connection_timeout)
+        .test_on_check_out(config.test_on_check_out)
+        .build(manager)?;
+    Ok(pool)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let map: HashMap<String, String> = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| map.get(name).cloned()
+    }
+
+    #[test]
+    fn test_from_vars_defaults() {
+        let config =
+            PoolConfig::from_vars(vars(&[(DATABASE_URL_VAR, "postgres://localhost/latin")]));
+        assert_eq!(
+            config.unwrap(),
+            PoolConfig::new("postgres://localhost/latin")
+        );
+    }
+
+    #[test]
+    fn test_from_vars_overrides() {
+        let config = PoolConfig::from_vars(vars(&[
+            (DATABASE_URL_VAR, "postgres://localhost/latin"),
+            (MAX_SIZE_VAR, "4"),
+            (MIN_IDLE_VAR, "1"),
+            (TIMEOUT_VAR, "5"),
+            (TEST_ON_CHECK_OUT_VAR, "false"),
+        ]))
+        .unwrap();
+        assert_eq!(config.max_size, 4);
+        assert_eq!(config.min_idle, Some(1));
+        assert_eq!(config.connection_timeout, Duration::from_secs(5));
+        assert!(!config.test_on_check_out);
+    }
+
+    #[test]
+    fn test_from_vars_errors() {
+        assert!(PoolConfig::from_vars(vars(&[])).is_err());
+        assert!(PoolConfig::from_vars(vars(&[
+            (DATABASE_URL_VAR, "postgres://localhost/latin"),
+            (MAX_SIZE_VAR, "lots"),
+        ]))
+        .is_err());
+    }
+
+    #[test]
+    fn test_lns_config_validate() {
+        let dir = env::temp_dir().join(format!("lns_config_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let config = LnsConfig::new(&dir);
+        match config.validate() {
+            Err(LatinDbError::LnsDirInvalid { missing, .. }) => {
+                assert_eq!(missing.len(), 25);
+                assert_eq!(missing[0], "a.xml");
+            }
+            other => panic!("expected LnsDirInvalid, got {:?}", other),
+        }
+        for file in XML_FILES.values() {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        assert!(config.validate().is_ok());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_try_get_connection_pool_rejects_bad_sizes() {
+        let config = PoolConfig {
+            max_size: 2,
+            min_idle: Some(3),
+            ..PoolConfig::new("postgres://localhost/latin")
+        };
+        assert!(matches!(
+            try_get_connection_pool(&config),
+            Err(LatinDbError::Config(_))
+        ));
+    }
+}
@@ -0,0 +1,237 @@
+use crate::enclitics::{split_enclitics, Compound};
+use crate::error::LatinDbError;
+use crate::membership::list_memberships;
+use crate::normalize::{fold_sql, normalize};
+use crate::tokenize::{Token, TokenKind};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::sql_types::{Array, Text};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// The lists a coverage report is broken down by, named as in
+/// `ListMembership`.
+pub const COVERAGE_LISTS: [&str; 3] = ["gcse", "asvocab", "clc"];
+
+/// A word from a passage and the headwords it is a form of.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Resolution {
+    pub token: Token,
+    /// Every headword the form could belong to; empty if it was not found.
+    pub headwords: Vec<String>,
+    /// Set when only the word left after removing an enclitic was found.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compound: Option<Compound>,
+}
+
+fn is_roman(token: &Token) -> bool {
+    token.kind == TokenKind::Numeral && token.text.chars().all(char::is_alphabetic)
+}
+
+#[derive(QueryableByName, Debug)]
+struct FormHead {
+    #[sql_type = "Text"]
+    form: String,
+    #[sql_type = "Text"]
+    headword: String,
+}
+
+/// Resolve the word tokens of a passage to their headwords in
+/// `lewis_short_lemmata`, in one query. A Roman numeral that is also a
+/// Latin form, like `VI` or `MI`, is resolved as a word; other numerals are
+/// skipped.
+///
+/// Each token is tried as written (normalized), then, if elided, with the
+/// endings it may have lost, then with an enclitic split off; the first
+/// spelling that is found wins.
+pub fn resolve_tokens(
+    tokens: &[Token],
+    connection: &PgConnection,
+) -> Result<Vec<Resolution>, LatinDbError> {
+    let words: Vec<&Token> = tokens
+        .iter()
+        .filter(|token| token.kind == TokenKind::Word || is_roman(token))
+        .collect();
+    let attempts: Vec<Vec<(String, Option<Compound>)>> = words
+        .iter()
+        .map(|token| {
+            let mut attempts: Vec<(String, Option<Compound>)> = token
+                .candidates()
+                .iter()
+                .map(|spelling| (normalize(spelling), None))
+                .collect();
+            for compound in split_enclitics(&token.text) {
+                attempts.push((normalize(&compound.stem), Some(compound)));
+            }
+            attempts
+        })
+        .collect();
+
+    let mut keys: Vec<&str> = attempts
+        .iter()
+        .flatten()
+        .map(|(key, _)| key.as_str())
+        .collect();
+    keys.sort_unstable();
+    keys.dedup();
+    let sql = format!(
+        "SELECT DISTINCT {form} AS form, headword FROM lewis_short_lemmata \
+         WHERE {form} = ANY($1) ORDER BY headword",
+        form = fold_sql("form")
+    );
+    let rows: Vec<FormHead> = diesel::sql_query(sql)
+        .bind::<Array<Text>, _>(&keys)
+        .load(connection)?;
+    let mut heads: HashMap<String, Vec<String>> = HashMap::new();
+    for row in rows {
+        heads.entry(row.form).or_default().push(row.headword);
+    }
+
+    let resolutions = words
+        .into_iter()
+        .zip(attempts)
+        .filter_map(|(token, attempts)| {
+            let found = attempts
+                .into_iter()
+                .find_map(|(key, compound)| heads.get(&key).map(|h| (h.clone(), compound)));
+            if found.is_none() && token.kind == TokenKind::Numeral {
+                return None;
+            }
+            let (headwords, compound) = found.unwrap_or_default();
+            Some(Resolution {
+                token: Token {
+                    kind: TokenKind::Word,
+                    ..token.clone()
+                },
+                headwords,
+                compound,
+            })
+        })
+        .collect();
+    Ok(resolutions)
+}
+
+/// How much of a passage one list covers.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ListCoverage {
+    pub list: String,
+    pub tokens_covered: usize,
+    pub token_percent: f64,
+    pub lemmas_covered: usize,
+    pub lemma_percent: f64,
+    /// The words not on this list, once each, in order of first appearance.
+    pub needs_gloss: Vec<String>,
+}
+
+/// How much of a passage a student who knows each list should recognise.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CoverageReport {
+    /// The number of words, not counting numerals.
+    pub tokens: usize,
+    /// The number of distinct headwords the words resolve to. An ambiguous
+    /// form counts towards every headword it could belong to.
+    pub lemmas: usize,
+    /// The words that resolved to no headword at all.
+    pub unresolved: Vec<String>,
+    pub lists: Vec<ListCoverage>,
+}
+
+fn percent(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        (part as f64 * 1000.0 / whole as f64).round() / 10.0
+    }
+}
+
+/// Keep the first spelling of each word, comparing normalized.
+fn first_appearances<'a>(words: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    words
+        .filter(|word| seen.insert(normalize(word)))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Work out, for each of `COVERAGE_LISTS`, what share of the passage's words
+/// and lemmas are on the list, and which words would need glossing.
+///
+/// A word counts as covered if any headword it could belong to is on the
+/// list; unresolved words are never covered.
+pub fn coverage(
+    tokens: &[Token],
+    connection: &PgConnection,
+) -> Result<CoverageReport, LatinDbError> {
+    let resolutions = resolve_tokens(tokens, connection)?;
+    let mut lemmas: Vec<String> = resolutions
+        .iter()
+        .flat_map(|r| r.headwords.iter().cloned())
+        .collect();
+    lemmas.sort();
+    lemmas.dedup();
+    let memberships: HashMap<String, _> = list_memberships(&lemmas, connection)?
+        .into_iter()
+        .map(|m| (m.headword.clone(), m))
+        .collect();
+
+    let lists = COVERAGE_LISTS
+        .iter()
+        .map(|list| {
+            let on_list =
+                |headword: &String| memberships.get(headword).is_some_and(|m| m.contains(list));
+            let covered: Vec<bool> = resolutions
+                .iter()
+                .map(|r| r.headwords.iter().any(on_list))
+                .collect();
+            let tokens_covered = covered.iter().filter(|c| **c).count();
+            let lemmas_covered = lemmas.iter().filter(|h| on_list(h)).count();
+            let needs_gloss = first_appearances(
+                resolutions
+                    .iter()
+                    .zip(&covered)
+                    .filter(|(_, covered)| !**covered)
+                    .map(|(r, _)| r.token.text.as_str()),
+            );
+            ListCoverage {
+                list: list.to_string(),
+                tokens_covered,
+                token_percent: percent(tokens_covered, resolutions.len()),
+                lemmas_covered,
+                lemma_percent: percent(lemmas_covered, lemmas.len()),
+                needs_gloss,
+            }
+        })
+        .collect();
+
+    Ok(CoverageReport {
+        tokens: resolutions.len(),
+        lemmas: lemmas.len(),
+        unresolved: first_appearances(
+            resolutions
+                .iter()
+                .filter(|r| r.headwords.is_empty())
+                .map(|r| r.token.text.as_str()),
+        ),
+        lists,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percent() {
+        assert_eq!(percent(1, 3), 33.3);
+        assert_eq!(percent(2, 2), 100.0);
+        assert_eq!(percent(0, 0), 0.0);
+    }
+
+    #[test]
+    fn test_first_appearances() {
+        assert_eq!(
+            first_appearances(["Servus", "amat", "servus", "iuvenem", "juvenem"].into_iter()),
+            vec!["Servus", "amat", "iuvenem"]
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use crate::normalize::normalize;
+use serde::Serialize;
+
+/// A particle written joined to the end of the word before it.
+struct Enclitic {
+    suffix: &'static str,
+    gloss: &'static str,
+    /// The only words the particle attaches to, or `None` for any word.
+    hosts: Option<&'static [&'static str]>,
+}
+
+/// The pronouns `cum` follows rather than precedes (`mecum`, `nobiscum`,
+/// `quocum`).
+const CUM_HOSTS: &[&str] = &[
+    "me", "te", "se", "nobis", "uobis", "quo", "qua", "qui", "quibus",
+];
+
+/// Tried in this order; the first whose remainder is found wins.
+const ENCLITICS: [Enclitic; 4] = [
+    Enclitic {
+        suffix: "que",
+        gloss: "and",
+        hosts: None,
+    },
+    Enclitic {
+        suffix: "ne",
+        gloss: "(introduces a question)",
+        hosts: None,
+    },
+    Enclitic {
+        suffix: "ue",
+        gloss: "or",
+        hosts: None,
+    },
+    Enclitic {
+        suffix: "cum",
+        gloss: "with",
+        hosts: Some(CUM_HOSTS),
+    },
+];
+
+/// The shortest word an enclitic is stripped from, so that `ne` or `que`
+/// standing alone are left as they are.
+const MIN_STEM_LENGTH: usize = 2;
+
+/// A form found only after splitting off an enclitic: `senatusque` is
+/// `senatus` + `que`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Compound {
+    /// The part of the form that was looked up.
+    pub stem: String,
+    /// The enclitic, as spelled in the form.
+    pub enclitic: String,
+    pub gloss: String,
+}
+
+/// Every way `term` might split into a word and an enclitic, most likely
+/// first. The comparison is on the normalized spelling, so `Senatusque`
+/// and `servosve`/`seruosue` are all recognised.
+pub fn split_enclitics(term: &str) -> Vec<Compound> {
+    let chars: Vec<char> = term.trim().chars().collect();
+    let mut splits = Vec::new();
+    for enclitic in ENCLITICS.iter() {
+        let length = enclitic.suffix.chars().count();
+        if chars.len() < length + MIN_STEM_LENGTH {
+            continue;
+        }
+        let (stem, suffix): (String, String) = (
+            chars[..chars.len() - length].iter().collect(),
+            chars[chars.len() - length..].iter().collect(),
+        );
+        if normalize(&suffix) != enclitic.suffix {
+            continue;
+        }
+        if let Some(hosts) = enclitic.hosts {
+            if !hosts.contains(&normalize(&stem).as_str()) {
+                continue;
+            }
+        }
+        splits.push(Compound {
+            stem,
+            enclitic: suffix,
+            gloss: enclitic.gloss.to_string(),
+        });
+    }
+    splits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stems(term: &str) -> Vec<(String, String)> {
+        split_enclitics(term)
+            .into_iter()
+            .map(|c| (c.stem, c.enclitic))
+            .collect()
+    }
+
+    #[test]
+    fn test_split_common_enclitics() {
+        assert_eq!(
+            stems("senatusque")[0],
+            ("senatus".to_string(), "que".to_string())
+        );
+        assert_eq!(
+            stems("Venitne"),
+            vec![("Venit".to_string(), "ne".to_string())]
+        );
+        assert_eq!(
+            stems("servosve")[0],
+            ("servos".to_string(), "ve".to_string())
+        );
+        assert!(stems("que").is_empty());
+        assert!(stems("servus").is_empty());
+    }
+
+    #[test]
+    fn test_split_cum_only_after_pronouns() {
+        assert_eq!(stems("mecum"), vec![("me".to_string(), "cum".to_string())]);
+        assert_eq!(
+            stems("vobiscum"),
+            vec![("vobis".to_string(), "cum".to_string())]
+        );
+        assert!(stems("servocum").is_empty());
+    }
+}
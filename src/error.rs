@@ -0,0 +1,105 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Everything that can go wrong while looking a term up.
+#[derive(Debug)]
+pub enum LatinDbError {
+    /// A query against Postgres failed.
+    Database(diesel::result::Error),
+    /// No connection could be checked out of (or built for) the pool.
+    Pool(diesel::r2d2::PoolError),
+    /// The pool settings are missing or invalid.
+    Config(String),
+    /// The Lewis & Short XML file for a key is not on disk.
+    LnsFileMissing(PathBuf),
+    /// No LNS data directory was given, by `LATIN_LNS_DIR` or otherwise.
+    LnsDirUnset,
+    /// The LNS data directory is missing some of the files in `XML_FILES`.
+    LnsDirInvalid { dir: PathBuf, missing: Vec<String> },
+    /// Reading an LNS XML file failed for some other reason.
+    Io(io::Error),
+    /// An LNS entry was found but could not be parsed.
+    XmlParse { key: String, message: String },
+    /// An LNS key does not start with a letter that has an XML file.
+    UnknownInitial(String),
+    /// A result could not be rendered as JSON.
+    Serialization(serde_json::Error),
+    /// A list name that is not one of the lists the operation works on.
+    UnknownList(String),
+    /// A review grade outside 0 to 5.
+    InvalidGrade(i16),
+}
+
+impl fmt::Display for LatinDbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LatinDbError::Database(e) => write!(f, "database error: {}", e),
+            LatinDbError::Pool(e) => write!(f, "connection pool error: {}", e),
+            LatinDbError::Config(message) => write!(f, "configuration error: {}", message),
+            LatinDbError::LnsFileMissing(path) => {
+                write!(f, "LNS file not found: {}", path.display())
+            }
+            LatinDbError::LnsDirUnset => {
+                write!(
+                    f,
+                    "no LNS data directory; set LATIN_LNS_DIR or pass --lns-dir"
+                )
+            }
+            LatinDbError::LnsDirInvalid { dir, missing } => write!(
+                f,
+                "LNS data directory {} is missing {}; set LATIN_LNS_DIR or pass --lns-dir",
+                dir.display(),
+                missing.join(", ")
+            ),
+            LatinDbError::Io(e) => write!(f, "could not read LNS file: {}", e),
+            LatinDbError::XmlParse { key, message } => {
+                write!(f, "could not parse LNS entry {}: {}", key, message)
+            }
+            LatinDbError::UnknownInitial(key) => {
+                write!(f, "no LNS file for the initial letter of {:?}", key)
+            }
+            LatinDbError::Serialization(e) => write!(f, "could not serialize result: {}", e),
+            LatinDbError::UnknownList(name) => write!(f, "unknown word list: {:?}", name),
+            LatinDbError::InvalidGrade(grade) => {
+                write!(f, "review grade must be between 0 and 5, not {}", grade)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LatinDbError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LatinDbError::Database(e) => Some(e),
+            LatinDbError::Pool(e) => Some(e),
+            LatinDbError::Io(e) => Some(e),
+            LatinDbError::Serialization(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<diesel::result::Error> for LatinDbError {
+    fn from(e: diesel::result::Error) -> Self {
+        LatinDbError::Database(e)
+    }
+}
+
+impl From<diesel::r2d2::PoolError> for LatinDbError {
+    fn from(e: diesel::r2d2::PoolError) -> Self {
+        LatinDbError::Pool(e)
+    }
+}
+
+impl From<io::Error> for LatinDbError {
+    fn from(e: io::Error) -> Self {
+        LatinDbError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for LatinDbError {
+    fn from(e: serde_json::Error) -> Self {
+        LatinDbError::Serialization(e)
+    }
+}
@@ -0,0 +1,254 @@
+use crate::error::LatinDbError;
+use crate::normalize::{fold_sql, normalize};
+use crate::sources::VOCAB_SOURCES;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::sql_types::{Array, Integer, Text};
+use serde::Serialize;
+
+/// The cost of an edit that is really just a spelling convention: u/v, i/j.
+const SPELLING_COST: f32 = 0.1;
+/// The cost of ae/oe for e, or of doubling or undoubling a consonant.
+const CHEAP_COST: f32 = 0.3;
+/// How many suggestions `did_you_mean` returns at most.
+const MAX_SUGGESTIONS: usize = 5;
+
+/// A known form or headword close to a term that matched nothing.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FuzzyMatch {
+    pub word: String,
+    pub headword: String,
+    pub distance: f32,
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y')
+}
+
+fn substitution_cost(a: char, b: char) -> f32 {
+    if a == b {
+        0.0
+    } else if matches!((a, b), ('u', 'v') | ('v', 'u') | ('i', 'j') | ('j', 'i')) {
+        SPELLING_COST
+    } else {
+        1.0
+    }
+}
+
+/// Whether `word[index]` repeats the consonant before or after it, so that
+/// adding or dropping it is a doubling slip (`comittere`, `committtere`).
+fn is_doubled(word: &[char], index: usize) -> bool {
+    let c = word[index];
+    !is_vowel(c) && ((index > 0 && word[index - 1] == c) || word.get(index + 1) == Some(&c))
+}
+
+/// A weighted Damerau-Levenshtein distance that treats the usual Latin
+/// spelling variations as cheap: u/v and i/j are nearly free, and ae or oe
+/// for e, or a doubled consonant for a single one, cost less than a typo.
+pub fn latin_distance(a: &str, b: &str) -> f32 {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let (n, m) = (a.len(), b.len());
+    let mut d = vec![vec![0.0f32; m + 1]; n + 1];
+    for i in 1..=n {
+        let cost = if is_doubled(&a, i - 1) {
+            CHEAP_COST
+        } else {
+            1.0
+        };
+        d[i][0] = d[i - 1][0] + cost;
+    }
+    for j in 1..=m {
+        let cost = if is_doubled(&b, j - 1) {
+            CHEAP_COST
+        } else {
+            1.0
+        };
+        d[0][j] = d[0][j - 1] + cost;
+    }
+    for i in 1..=n {
+        for j in 1..=m {
+            let delete = if is_doubled(&a, i - 1) {
+                CHEAP_COST
+            } else {
+                1.0
+            };
+            let insert = if is_doubled(&b, j - 1) {
+                CHEAP_COST
+            } else {
+                1.0
+            };
+            let mut best = (d[i - 1][j] + delete)
+                .min(d[i][j - 1] + insert)
+                .min(d[i - 1][j - 1] + substitution_cost(a[i - 1], b[j - 1]));
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(d[i - 2][j - 2] + 1.0);
+            }
+            // ae/oe in one word against e in the other.
+            if i > 1 && b[j - 1] == 'e' && a[i - 1] == 'e' && matches!(a[i - 2], 'a' | 'o') {
+                best = best.min(d[i - 2][j - 1] + CHEAP_COST);
+            }
+            if j > 1 && a[i - 1] == 'e' && b[j - 1] == 'e' && matches!(b[j - 2], 'a' | 'o') {
+                best = best.min(d[i - 1][j - 2] + CHEAP_COST);
+            }
+            d[i][j] = best;
+        }
+    }
+    d[n][m]
+}
+
+/// The furthest a suggestion may be from a term of `length` letters.
+fn max_distance(length: usize) -> f32 {
+    1.0 + length as f32 / 6.0
+}
+
+/// The initial letters a candidate may start with, allowing for u/v, i/j
+/// and ae/oe/e.
+fn initials(term: &str) -> Vec<String> {
+    let first = match term.chars().next() {
+        Some(c) => c.to_lowercase().next().unwrap_or(c),
+        None => return Vec::new(),
+    };
+    let letters: &[char] = match first {
+        'u' | 'v' => &['u', 'v'],
+        'i' | 'j' => &['i', 'j'],
+        'a' | 'o' | 'e' => &['a', 'o', 'e'],
+        _ => return vec![first.to_string()],
+    };
+    letters.iter().map(|c| c.to_string()).collect()
+}
+
+#[derive(QueryableByName, Debug)]
+struct Candidate {
+    #[sql_type = "Text"]
+    word: String,
+    #[sql_type = "Text"]
+    headword: String,
+}
+
+/// The words starting with one of the folded letters in `$1` and between
+/// `$2` and `$3` letters long. Each source is filtered on its own, so the
+/// `(left(latin_fold(column), 1), length(column))` indexes answer it.
+fn candidate_sql() -> String {
+    let filter = |column: &str| {
+        format!(
+            "left({}, 1) = ANY($1) AND length({}) BETWEEN $2 AND $3",
+            fold_sql(column),
+            column
+        )
+    };
+    let mut branches = vec![format!(
+        "SELECT form AS word, headword FROM lewis_short_lemmata WHERE {}",
+        filter("form")
+    )];
+    for source in VOCAB_SOURCES {
+        branches.push(format!(
+            "SELECT v.headword AS word, v.headword FROM ({}) v WHERE {}",
+            source.sql_select(),
+            filter("v.headword")
+        ));
+    }
+    branches.join(" UNION ")
+}
+
+/// Suggest known forms and headwords close to `term`, nearest first.
+///
+/// Candidates are limited to words starting with the same letter (allowing
+/// for the spelling variants above) and of a similar length, so a typo in
+/// the first letter will not be caught.
+///
+/// With `exact`, as for an exact lookup, the term is compared as typed, so
+/// a word spelled differently but normalizing to the same key can be
+/// suggested; otherwise such words are taken to be the term itself.
+pub fn did_you_mean(
+    term: &str,
+    exact: bool,
+    connection: &PgConnection,
+) -> Result<Vec<FuzzyMatch>, LatinDbError> {
+    let folded = normalize(term);
+    let term = if exact { term.trim() } else { folded.as_str() };
+    let length = term.chars().count();
+    if length == 0 {
+        return Ok(Vec::new());
+    }
+    let limit = max_distance(length);
+    let slack = limit.ceil() as i32;
+    let candidates: Vec<Candidate> = diesel::sql_query(candidate_sql())
+        .bind::<Array<Text>, _>(initials(&folded))
+        .bind::<Integer, _>((length as i32 - slack).max(1))
+        .bind::<Integer, _>(length as i32 + slack)
+        .load(connection)?;
+
+    let mut matches: Vec<FuzzyMatch> = candidates
+        .into_iter()
+        .filter(|c| {
+            if exact {
+                c.word != term
+            } else {
+                normalize(&c.word) != term
+            }
+        })
+        .map(|c| FuzzyMatch {
+            distance: latin_distance(term, &c.word),
+            word: c.word,
+            headword: c.headword,
+        })
+        .filter(|m| m.distance <= limit)
+        .collect();
+    matches.sort_by(|a, b| {
+        a.distance
+            .total_cmp(&b.distance)
+            .then_with(|| a.word.cmp(&b.word))
+            .then_with(|| a.headword.cmp(&b.headword))
+    });
+    matches.truncate(MAX_SUGGESTIONS);
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn test_latin_distance_plain_typos() {
+        assert!(close(latin_distance("dominus", "dominus"), 0.0));
+        assert!(close(latin_distance("domnius", "dominus"), 1.0));
+        assert!(close(latin_distance("dominos", "dominus"), 1.0));
+        assert!(close(latin_distance("domius", "dominus"), 1.0));
+    }
+
+    #[test]
+    fn test_latin_distance_spelling_variants() {
+        assert!(close(
+            latin_distance("iuvenis", "juuenis"),
+            2.0 * SPELLING_COST
+        ));
+        assert!(close(latin_distance("caelum", "celum"), CHEAP_COST));
+        assert!(close(latin_distance("poena", "pena"), CHEAP_COST));
+        assert!(close(latin_distance("comitto", "committo"), CHEAP_COST));
+        assert!(close(latin_distance("Iuvenis", "iuvenis"), 0.0));
+    }
+
+    #[test]
+    fn test_candidate_sql_filters_each_source() {
+        let sql = candidate_sql();
+        assert!(sql.contains("left(latin_fold(form), 1) = ANY($1) AND length(form)"));
+        assert_eq!(
+            sql.matches("left(latin_fold(v.headword), 1) = ANY($1)")
+                .count(),
+            VOCAB_SOURCES.len()
+        );
+        assert!(!sql.contains("lower(left("));
+    }
+
+    #[test]
+    fn test_initials() {
+        assert_eq!(initials("Vir"), vec!["u", "v"]);
+        assert_eq!(initials("equus"), vec!["a", "o", "e"]);
+        assert_eq!(initials("servus"), vec!["s"]);
+    }
+}
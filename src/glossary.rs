@@ -0,0 +1,384 @@
+use crate::coverage::resolve_tokens;
+use crate::error::LatinDbError;
+use crate::lns_store::{load_entries_by_key, EntryKey};
+use crate::membership::list_memberships;
+use crate::parsing::LnsEntry;
+use crate::sources::vocab_source;
+use crate::tokenize::Token;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::sql_types::{Array, Nullable, Text};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+/// The sources a gloss is taken from, best first. LNS is tried after these.
+const GLOSS_SOURCES: [&str; 3] = ["gcse", "asvocab", "wwords"];
+
+/// One line of a running vocabulary.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct GlossEntry {
+    pub headword: String,
+    pub dict_form: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub part_of_speech: Option<String>,
+    pub meaning: String,
+    /// Where the gloss came from: a source name, `"lns"`, or `None` if the
+    /// word was not found anywhere and needs glossing by hand.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Why the LNS entry for the word could not be read, if it could not.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(QueryableByName, Debug)]
+struct GlossRow {
+    #[sql_type = "Text"]
+    source: String,
+    #[sql_type = "Text"]
+    headword: String,
+    #[sql_type = "Text"]
+    dict_form: String,
+    #[sql_type = "Nullable<Text>"]
+    part_of_speech: Option<String>,
+    #[sql_type = "Text"]
+    meaning: String,
+}
+
+fn gloss_sql() -> String {
+    let branches: Vec<String> = GLOSS_SOURCES
+        .iter()
+        .filter_map(|name| vocab_source(name))
+        .map(|source| {
+            format!(
+                "SELECT '{}' AS source, v.id, v.headword, v.dict_form, v.part_of_speech, v.meaning \
+                 FROM ({}) v WHERE v.headword = ANY($1)",
+                source.name(),
+                source.sql_select()
+            )
+        })
+        .collect();
+    format!("{} ORDER BY headword, id", branches.join(" UNION ALL "))
+}
+
+#[derive(QueryableByName, Debug)]
+struct HeadKey {
+    #[sql_type = "Text"]
+    head: String,
+    #[sql_type = "Text"]
+    xml_id: String,
+    #[sql_type = "Text"]
+    key: String,
+}
+
+/// The LNS entries found for a headword, and why any could not be read.
+#[derive(Debug, Default)]
+struct LnsFound {
+    entries: Vec<LnsEntry>,
+    error: Option<String>,
+}
+
+/// Every LNS entry, homographs included, for each of `headwords` that has
+/// one, with the error for any whose entries could not be read. The keys
+/// are found in one query and the entries read in one batch.
+fn lns_entries_for(
+    headwords: &[String],
+    connection: &PgConnection,
+) -> Result<HashMap<String, LnsFound>, LatinDbError> {
+    let rows: Vec<HeadKey> = diesel::sql_query(
+        "SELECT DISTINCT m.head, k.simple_key, k.xml_id, k.key FROM lns_entry_keys m \
+         JOIN lns_entry_keys k ON k.simple_key = m.simple_key \
+         WHERE m.head = ANY($1) ORDER BY m.head, k.simple_key, k.key",
+    )
+    .bind::<Array<Text>, _>(headwords)
+    .load(connection)?;
+
+    let mut keys: Vec<EntryKey> = Vec::new();
+    for row in &rows {
+        let entry_key = EntryKey {
+            xml_id: row.xml_id.clone(),
+            key: row.key.clone(),
+        };
+        if !keys.contains(&entry_key) {
+            keys.push(entry_key);
+        }
+    }
+    let loaded: HashMap<String, Result<Vec<LnsEntry>, LatinDbError>> = keys
+        .iter()
+        .map(|k| k.xml_id.clone())
+        .zip(load_entries_by_key(&keys, connection)?)
+        .collect();
+
+    let mut found: HashMap<String, LnsFound> = HashMap::new();
+    for row in rows {
+        let head = found.entry(row.head).or_default();
+        match loaded.get(&row.xml_id) {
+            Some(Ok(entries)) => head.entries.extend(entries.iter().cloned()),
+            Some(Err(e)) => head.error = Some(e.to_string()),
+            None => {}
+        }
+    }
+    Ok(found)
+}
+
+/// The best gloss LNS offers: the glosses before the first sense, or else
+/// those of the first sense that has any.
+fn lns_gloss(headword: &str, entries: &[LnsEntry]) -> Option<GlossEntry> {
+    entries.iter().find_map(|entry| {
+        let (part_of_speech, tags) = if entry.early_i_tags.is_empty() {
+            let sense = entry.senses.iter().find(|s| !s.i_tags.is_empty())?;
+            (sense.pos.clone(), &sense.i_tags)
+        } else {
+            (None, &entry.early_i_tags)
+        };
+        Some(GlossEntry {
+            headword: headword.to_string(),
+            dict_form: entry.head.clone(),
+            part_of_speech,
+            meaning: tags.join(", "),
+            source: Some("lns".to_string()),
+            error: None,
+        })
+    })
+}
+
+/// Build a running vocabulary for a passage: one entry per headword, in the
+/// order the words first appear.
+///
+/// Glosses come from `gcse_latin`, then `asvocab`, then Whitaker's Words,
+/// then the Lewis and Short entry. An ambiguous form lists every headword it
+/// could belong to. Words not found at all are listed under their own
+/// spelling with an empty meaning, and words whose LNS entry could not be
+/// read carry the reason in `error`. If `omit` names a vocabulary source,
+/// headwords on that list are left out; any other name is an
+/// `UnknownList` error.
+pub fn glossary(
+    tokens: &[Token],
+    omit: Option<&str>,
+    connection: &PgConnection,
+) -> Result<Vec<GlossEntry>, LatinDbError> {
+    if let Some(list) = omit {
+        if vocab_source(list).is_none() {
+            return Err(LatinDbError::UnknownList(list.to_string()));
+        }
+    }
+    let resolutions = resolve_tokens(tokens, connection)?;
+
+    let mut seen = HashSet::new();
+    let mut headwords: Vec<String> = Vec::new();
+    let mut unresolved: HashSet<String> = HashSet::new();
+    for resolution in &resolutions {
+        if resolution.headwords.is_empty() {
+            let word = resolution.token.text.to_lowercase();
+            if seen.insert(word.clone()) {
+                unresolved.insert(word.clone());
+                headwords.push(word);
+            }
+        }
+        for headword in &resolution.headwords {
+            if seen.insert(headword.clone()) {
+                headwords.push(headword.clone());
+            }
+        }
+    }
+
+    if let Some(list) = omit {
+        let known: HashSet<String> = list_memberships(&headwords, connection)?
+            .into_iter()
+            .filter(|m| m.contains(list))
+            .map(|m| m.headword)
+            .collect();
+        headwords.retain(|h| !known.contains(h));
+    }
+
+    let rows: Vec<GlossRow> = diesel::sql_query(gloss_sql())
+        .bind::<Array<Text>, _>(&headwords)
+        .load(connection)?;
+    let mut best: HashMap<String, GlossRow> = HashMap::new();
+    for row in rows {
+        let rank = |source: &str| GLOSS_SOURCES.iter().position(|s| *s == source);
+        let better = best
+            .get(&row.headword)
+            .is_none_or(|current| rank(&row.source) < rank(&current.source));
+        if better {
+            best.insert(row.headword.clone(), row);
+        }
+    }
+
+    let missing: Vec<String> = headwords
+        .iter()
+        .filter(|h| !best.contains_key(*h) && !unresolved.contains(*h))
+        .cloned()
+        .collect();
+    let mut lns = lns_entries_for(&missing, connection)?;
+
+    let mut entries = Vec::new();
+    for headword in headwords {
+        let entry = match best.remove(&headword) {
+            Some(row) => GlossEntry {
+                headword: row.headword,
+                dict_form: row.dict_form,
+                part_of_speech: row.part_of_speech,
+                meaning: row.meaning,
+                source: Some(row.source),
+                error: None,
+            },
+            None => {
+                let found = lns.remove(&headword).unwrap_or_default();
+                let entry = lns_gloss(&headword, &found.entries).unwrap_or_else(|| GlossEntry {
+                    dict_form: headword.clone(),
+                    headword,
+                    part_of_speech: None,
+                    meaning: String::new(),
+                    source: None,
+                    error: None,
+                });
+                GlossEntry {
+                    error: found.error,
+                    ..entry
+                }
+            }
+        };
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// The layouts a glossary can be printed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlossFormat {
+    Markdown,
+    Csv,
+    Latex,
+}
+
+impl FromStr for GlossFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "markdown" | "md" => Ok(GlossFormat::Markdown),
+            "csv" => Ok(GlossFormat::Csv),
+            "latex" | "tex" => Ok(GlossFormat::Latex),
+            _ => Err(format!("unknown format: {:?}", s)),
+        }
+    }
+}
+
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn latex_escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Lay out a glossary as a table of dictionary form, part of speech and
+/// meaning. The LaTeX version is a `longtable`, so it can run over pages.
+pub fn render(entries: &[GlossEntry], format: GlossFormat) -> String {
+    let mut out = String::new();
+    match format {
+        GlossFormat::Markdown => {
+            out.push_str("| Word | Part of speech | Meaning |\n");
+            out.push_str("| --- | --- | --- |\n");
+            for entry in entries {
+                out.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    markdown_cell(&entry.dict_form),
+                    markdown_cell(entry.part_of_speech.as_deref().unwrap_or_default()),
+                    markdown_cell(&entry.meaning)
+                ));
+            }
+        }
+        GlossFormat::Csv => {
+            out.push_str("headword,dict_form,part_of_speech,meaning\n");
+            for entry in entries {
+                let fields = [
+                    entry.headword.as_str(),
+                    entry.dict_form.as_str(),
+                    entry.part_of_speech.as_deref().unwrap_or_default(),
+                    entry.meaning.as_str(),
+                ];
+                let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+                out.push_str(&fields.join(","));
+                out.push('\n');
+            }
+        }
+        GlossFormat::Latex => {
+            out.push_str("% Needs \\usepackage{longtable}\n");
+            out.push_str(
+                "\\begin{longtable}{p{0.3\\textwidth}p{0.15\\textwidth}p{0.45\\textwidth}}\n",
+            );
+            for entry in entries {
+                out.push_str(&format!(
+                    "\\textbf{{{}}} & \\textit{{{}}} & {} \\\\\n",
+                    latex_escape(&entry.dict_form),
+                    latex_escape(entry.part_of_speech.as_deref().unwrap_or_default()),
+                    latex_escape(&entry.meaning)
+                ));
+            }
+            out.push_str("\\end{longtable}\n");
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(dict_form: &str, meaning: &str) -> GlossEntry {
+        GlossEntry {
+            headword: "manus".to_string(),
+            dict_form: dict_form.to_string(),
+            part_of_speech: Some("noun".to_string()),
+            meaning: meaning.to_string(),
+            source: Some("gcse".to_string()),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_render_escapes() {
+        let entries = vec![entry("manus, -us, f.", "hand; \"band\" & 100%")];
+        let csv = render(&entries, GlossFormat::Csv);
+        assert!(csv.ends_with("manus,\"manus, -us, f.\",noun,\"hand; \"\"band\"\" & 100%\"\n"));
+        let latex = render(&entries, GlossFormat::Latex);
+        assert!(latex.contains(
+            "\\textbf{manus, -us, f.} & \\textit{noun} & hand; \"band\" \\& 100\\% \\\\"
+        ));
+        let markdown = render(&[entry("a|b", "c")], GlossFormat::Markdown);
+        assert!(markdown.ends_with("| a\\|b | noun | c |\n"));
+    }
+
+    #[test]
+    fn test_gloss_sql_prefers_lists() {
+        let sql = gloss_sql();
+        let gcse = sql.find("'gcse'").unwrap();
+        let wwords = sql.find("'wwords'").unwrap();
+        assert!(gcse < wwords);
+        assert!(!sql.contains("'clc'"));
+    }
+}
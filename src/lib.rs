@@ -1,3 +1,7 @@
+// diesel 1.4's `table!` and derive macros expand to impl blocks nested in
+// anonymous consts, which newer compilers warn about.
+#![allow(non_local_definitions)]
+
 #[macro_use]
 extern crate diesel;
 
@@ -6,294 +10,182 @@ use diesel::prelude::*;
 use diesel::r2d2::ConnectionManager;
 use diesel::r2d2::Pool;
 // use dotenv;
-use std::env;
 // use diesel::result::Error;
 // use crate::search::XML_FILES;
-// use serde::Serialize;
-use serde_json;
 
+pub mod aggregate;
+pub mod analysis;
+pub mod anki;
+pub mod config;
+pub mod coverage;
+pub mod enclitics;
+pub mod error;
+pub mod fuzzy;
+pub mod glossary;
+pub mod lns_cache;
+pub mod lns_index;
+pub mod lns_store;
+pub mod membership;
 pub mod models;
+pub mod normalize;
+pub mod options;
 pub mod parsing;
+pub mod quiz;
+pub mod reverse;
 pub mod schema;
 pub mod search;
-
-// use search::query_lns;
-use search::query_lns_vec;
-
-pub type QueryFunc = fn(&str, &PgConnection) -> Result<String, Box<dyn std::error::Error>>;
-
-pub fn get_connection_pool() -> Pool<ConnectionManager<PgConnection>> {
+pub mod sources;
+pub mod srs;
+pub mod suggest;
+pub mod tokenize;
+
+pub use aggregate::{lookup_form, lookup_many, resolve_form_threaded};
+pub use analysis::Analysis;
+pub use config::{try_get_connection_pool, LnsConfig, PoolConfig};
+pub use enclitics::{split_enclitics, Compound};
+pub use error::LatinDbError;
+pub use fuzzy::{did_you_mean, FuzzyMatch};
+pub use lns_cache::{warm_lns_cache, CacheStats};
+pub use membership::{form_membership, list_membership, list_memberships, ListMembership};
+pub use models::{LookupResult, SourceResults, VocabHit};
+pub use normalize::normalize;
+pub use options::QueryOptions;
+pub use parsing::LnsEntry;
+pub use reverse::{reverse_lookup, ReverseHit};
+pub use search::{configure_lns, configure_lns_for_lookups, lns_cache_stats, lns_index_error};
+pub use sources::{list_rows, vocab_source, VocabSource, VOCAB_SOURCES};
+pub use suggest::{suggest, Suggestion};
+
+use lns_store::{load_entries, EntryKey};
+use normalize::column_matches;
+use std::thread;
+
+pub type DbPool = Pool<ConnectionManager<PgConnection>>;
+
+/// Build a pool from the `LATIN_PG_*` environment variables, panicking on
+/// failure. Long-running callers should prefer `try_get_connection_pool`.
+pub fn get_connection_pool() -> DbPool {
     // dotenv::from_path("/home/simon/.api_keys").expect("api_keys not accessible");
-    let url = env::var("LATIN_PG_DATABASE_URL").expect("LATIN_PG_DATABASE_URL must be set");
-    // let url = dotenv::var("LATIN_PG_DATABASE_URL").expect("LATIN_PG_DATABASE_URL must be set");
-
-    let manager = ConnectionManager::<PgConnection>::new(url);
-    Pool::builder()
-        .max_size(25)
-        .test_on_check_out(true)
-        .build(manager)
-        .expect("Could not build connection pool")
-}
-
-// #[derive(Queryable, Debug, PartialEq, Clone, Serialize)]
-// struct QueryResult {
-//     dict_form: String,
-//     part_of_speech: String,
-//     meaning: String,
-// }
-
-pub fn query_gcse_latin(
-    term: &str,
-    connection: &PgConnection,
-) -> Result<String, Box<dyn std::error::Error>> {
-    use self::schema::gcse_latin::dsl::dict_form as g_dict_form;
-    use self::schema::gcse_latin::dsl::gcse_latin;
-    use self::schema::gcse_latin::dsl::headword as g_headword;
-    use self::schema::gcse_latin::dsl::meaning as g_meaning;
-    use self::schema::gcse_latin::dsl::part_of_speech as g_part_of_speech;
-    use self::schema::lewis_short_lemmata::dsl::*;
-
-    let data: Result<Vec<(String, String, String)>, _> = gcse_latin
-        .inner_join(lewis_short_lemmata.on(g_headword.eq(headword)))
-        .filter(form.eq(term))
-        .select((g_dict_form, g_part_of_speech, g_meaning))
-        .order(g_dict_form.asc())
-        .load(connection);
-
-    match data {
-        Ok(results) => match serde_json::to_string(&results) {
-            Ok(json) => Ok(format!("\"gcse\": {}", json)),
-            Err(e) => Err(Box::new(e)),
-        },
-        Err(e) => Err(Box::new(e)),
-    }
-}
-
-pub fn query_clc4(
-    term: &str,
-    connection: &PgConnection,
-) -> Result<String, Box<dyn std::error::Error>> {
-    use self::schema::clc4::dsl::clc4;
-    use self::schema::clc4::dsl::dict_form as c_dict_form;
-    use self::schema::clc4::dsl::headword as c_headword;
-    use self::schema::clc4::dsl::meaning as c_meaning;
-    use self::schema::lewis_short_lemmata::dsl::*;
-
-    let data: Result<Vec<(String, String)>, _> = clc4
-        .inner_join(lewis_short_lemmata.on(c_headword.eq(headword)))
-        .filter(form.eq(term))
-        .select((c_dict_form, c_meaning))
-        .order(c_dict_form.asc())
-        .load(connection);
-
-    match data {
-        Ok(results) => match serde_json::to_string(&results) {
-            Ok(json) => Ok(format!("\"clc\": {}", json)),
-            Err(e) => Err(Box::new(e)),
-        },
-        Err(e) => Err(Box::new(e)),
-    }
-}
-
-pub fn query_asvocab(
-    term: &str,
-    connection: &PgConnection,
-) -> Result<String, Box<dyn std::error::Error>> {
-    use self::schema::asvocab::dsl::asvocab;
-    use self::schema::asvocab::dsl::dict_form as a_dict_form;
-    use self::schema::asvocab::dsl::headword as a_headword;
-    use self::schema::asvocab::dsl::meaning as a_meaning;
-    use self::schema::asvocab::dsl::part_of_speech as a_part_of_speech;
-    use self::schema::lewis_short_lemmata::dsl::*;
-
-    let data: Result<Vec<(String, String, String)>, _> = asvocab
-        .inner_join(lewis_short_lemmata.on(a_headword.eq(headword)))
-        .filter(form.eq(term))
-        .select((a_dict_form, a_part_of_speech, a_meaning))
-        .order(a_dict_form.asc())
-        .load(connection);
-
-    match data {
-        Ok(results) => match serde_json::to_string(&results) {
-            Ok(json) => Ok(format!("\"asvocab\": {}", json)),
-            Err(e) => Err(Box::new(e)),
-        },
-        Err(e) => Err(Box::new(e)),
-    }
-}
-
-pub fn query_wwords(
-    term: &str,
-    connection: &PgConnection,
-) -> Result<String, Box<dyn std::error::Error>> {
-    use self::schema::lewis_short_lemmata::dsl::*;
-    use self::schema::wwords::dsl::class as w_class;
-    use self::schema::wwords::dsl::dict_form as w_dict_form;
-    use self::schema::wwords::dsl::headword as w_headword;
-    use self::schema::wwords::dsl::meaning as w_meaning;
-    use self::schema::wwords::dsl::part_of_speech as w_part_of_speech;
-    use self::schema::wwords::dsl::wwords;
-
-    let data: Result<Vec<(String, String, Option<String>, String)>, _> = wwords
-        .inner_join(lewis_short_lemmata.on(w_headword.eq(headword)))
-        .filter(form.eq(term))
-        .select((w_dict_form, w_part_of_speech, w_class, w_meaning))
-        .order(w_dict_form.asc())
-        .load(connection);
-
-    match data {
-        Ok(results) => match serde_json::to_string(&results) {
-            Ok(json) => Ok(format!("\"wwords\": {}", json)),
-            Err(e) => Err(Box::new(e)),
-        },
-        Err(e) => Err(Box::new(e)),
-    }
+    let config = PoolConfig::from_env().expect("LATIN_PG_DATABASE_URL must be set");
+    try_get_connection_pool(&config).expect("Could not build connection pool")
 }
 
+/// Every LNS entry for a headword that `term` is a form of, homographs
+/// included, each tagged with its `xml_id` and homograph number.
 pub fn get_lns_key(
     term: &str,
+    exact: bool,
     connection: &PgConnection,
-) -> Result<String, Box<dyn std::error::Error>> {
+) -> Result<Vec<LnsEntry>, LatinDbError> {
     use self::schema::lewis_short_lemmata::dsl::*;
     use self::schema::lns_entry_keys::dsl::*;
 
-    let data: Result<Vec<String>, _> = lns_entry_keys
+    let matched: Vec<String> = lns_entry_keys
         .inner_join(lewis_short_lemmata.on(headword.eq(head)))
-        .filter(form.eq(term))
-        .select(key)
-        .load(connection);
+        .filter(column_matches(form, term, exact))
+        .select(simple_key)
+        .distinct()
+        .load(connection)?;
 
-    match data {
-        Ok(results) => match query_lns_vec(results) {
-            Ok(parsed_entries) => Ok(format!("\"lns\": {}", parsed_entries)),
-            Err(e) => Err(e),
-        },
-        Err(e) => Err(Box::new(e)),
-    }
+    load_homographs(&matched, connection)
 }
 
-pub fn query_gcse_latin_headword(
+/// Every LNS entry whose headword is `term`, homographs included.
+pub fn get_lns_key_headword(
     term: &str,
+    exact: bool,
     connection: &PgConnection,
-) -> Result<String, Box<dyn std::error::Error>> {
-    use self::schema::gcse_latin::dsl::dict_form as g_dict_form;
-    use self::schema::gcse_latin::dsl::gcse_latin;
-    use self::schema::gcse_latin::dsl::headword as g_headword;
-    use self::schema::gcse_latin::dsl::meaning as g_meaning;
-    use self::schema::gcse_latin::dsl::part_of_speech as g_part_of_speech;
+) -> Result<Vec<LnsEntry>, LatinDbError> {
+    use self::schema::lns_entry_keys::dsl::*;
 
-    let data: Result<Vec<(String, String, String)>, _> = gcse_latin
-        .filter(g_headword.eq(term))
-        .select((g_dict_form, g_part_of_speech, g_meaning))
-        .order(g_dict_form.asc())
-        .load(connection);
+    let matched: Vec<String> = lns_entry_keys
+        .filter(column_matches(head, term, exact))
+        .select(simple_key)
+        .distinct()
+        .load(connection)?;
 
-    match data {
-        Ok(results) => match serde_json::to_string(&results) {
-            Ok(json) => Ok(format!("\"gcse\": {}", json)),
-            Err(e) => Err(Box::new(e)),
-        },
-        Err(e) => Err(Box::new(e)),
-    }
+    load_homographs(&matched, connection)
 }
 
-pub fn query_clc4_headword(
-    term: &str,
+/// Load every entry with one of the `simple_keys`, so that all the
+/// homographs of a word come back together, in key order (`liber1` before
+/// `liber2`).
+fn load_homographs(
+    simple_keys: &[String],
     connection: &PgConnection,
-) -> Result<String, Box<dyn std::error::Error>> {
-    use self::schema::clc4::dsl::clc4;
-    use self::schema::clc4::dsl::dict_form as c_dict_form;
-    use self::schema::clc4::dsl::headword as c_headword;
-    use self::schema::clc4::dsl::meaning as c_meaning;
+) -> Result<Vec<LnsEntry>, LatinDbError> {
+    use self::schema::lns_entry_keys::dsl::*;
 
-    let data: Result<Vec<(String, String)>, _> = clc4
-        .filter(c_headword.eq(term))
-        .select((c_dict_form, c_meaning))
-        .order(c_dict_form.asc())
-        .load(connection);
+    let results: Vec<EntryKey> = lns_entry_keys
+        .filter(simple_key.eq_any(simple_keys))
+        .select((xml_id, key))
+        .order((simple_key, key))
+        .load(connection)?;
 
-    match data {
-        Ok(results) => match serde_json::to_string(&results) {
-            Ok(json) => Ok(format!("\"clc\": {}", json)),
-            Err(e) => Err(Box::new(e)),
-        },
-        Err(e) => Err(Box::new(e)),
-    }
+    load_entries(&results, connection)
 }
 
-pub fn query_asvocab_headword(
-    term: &str,
-    connection: &PgConnection,
-) -> Result<String, Box<dyn std::error::Error>> {
-    use self::schema::asvocab::dsl::asvocab;
-    use self::schema::asvocab::dsl::dict_form as a_dict_form;
-    use self::schema::asvocab::dsl::headword as a_headword;
-    use self::schema::asvocab::dsl::meaning as a_meaning;
-    use self::schema::asvocab::dsl::part_of_speech as a_part_of_speech;
-
-    let data: Result<Vec<(String, String, String)>, _> = asvocab
-        .filter(a_headword.eq(term))
-        .select((a_dict_form, a_part_of_speech, a_meaning))
-        .order(a_dict_form.asc())
-        .load(connection);
-
-    match data {
-        Ok(results) => match serde_json::to_string(&results) {
-            Ok(json) => Ok(format!("\"asvocab\": {}", json)),
-            Err(e) => Err(Box::new(e)),
-        },
-        Err(e) => Err(Box::new(e)),
-    }
+/// Ask every source about `term` on its own pooled connection and thread.
+///
+/// A failure in one source (a missing LNS file, say) is reported in that
+/// source's slot rather than aborting the others.
+pub fn lookup_form_threaded(term: &str, options: &QueryOptions, pool: &DbPool) -> SourceResults {
+    run_threaded(
+        term,
+        pool,
+        |source, term, conn| source.lookup_form(term, options, conn),
+        |term, conn| get_lns_key(term, options.exact, conn),
+    )
 }
 
-pub fn query_wwords_headword(
-    term: &str,
-    connection: &PgConnection,
-) -> Result<String, Box<dyn std::error::Error>> {
-    use self::schema::wwords::dsl::class as w_class;
-    use self::schema::wwords::dsl::dict_form as w_dict_form;
-    use self::schema::wwords::dsl::headword as w_headword;
-    use self::schema::wwords::dsl::meaning as w_meaning;
-    use self::schema::wwords::dsl::part_of_speech as w_part_of_speech;
-    use self::schema::wwords::dsl::wwords;
-
-    let data: Result<Vec<(String, String, Option<String>, String)>, _> = wwords
-        .filter(w_headword.eq(term))
-        .select((w_dict_form, w_part_of_speech, w_class, w_meaning))
-        .order(w_dict_form.asc())
-        .load(connection);
-
-    match data {
-        Ok(results) => match serde_json::to_string(&results) {
-            Ok(json) => Ok(format!("\"wwords\": {}", json)),
-            Err(e) => Err(Box::new(e)),
-        },
-        Err(e) => Err(Box::new(e)),
-    }
+/// As `lookup_form_threaded`, but matching `term` against headwords.
+pub fn lookup_headword_threaded(term: &str, exact: bool, pool: &DbPool) -> SourceResults {
+    run_threaded(
+        term,
+        pool,
+        |source, term, conn| source.lookup_headword(term, exact, conn),
+        |term, conn| get_lns_key_headword(term, exact, conn),
+    )
 }
-pub fn get_lns_key_headword(
-    term: &str,
-    connection: &PgConnection,
-) -> Result<String, Box<dyn std::error::Error>> {
-    use self::schema::lns_entry_keys::dsl::*;
 
-    let data: Result<Vec<String>, _> = lns_entry_keys
-        .filter(head.eq(term))
-        .select(key)
-        .load(connection);
+fn run_threaded<V, L>(term: &str, pool: &DbPool, vocab_query: V, lns_query: L) -> SourceResults
+where
+    V: Fn(&dyn VocabSource, &str, &PgConnection) -> Result<Vec<VocabHit>, LatinDbError> + Sync,
+    L: Fn(&str, &PgConnection) -> Result<Vec<LnsEntry>, LatinDbError> + Sync,
+{
+    let vocab_query = &vocab_query;
+    let lns_query = &lns_query;
+    thread::scope(|scope| {
+        let mut threads = vec![];
+        for source in VOCAB_SOURCES {
+            threads.push(scope.spawn(move || {
+                let hits = pool
+                    .get()
+                    .map_err(LatinDbError::from)
+                    .and_then(|conn| vocab_query(source, term, &conn));
+                (source.name(), hits)
+            }));
+        }
 
-    match data {
-        Ok(results) => match query_lns_vec(results) {
-            Ok(parsed_entries) => Ok(format!("\"lns\": {}", parsed_entries)),
-            Err(e) => Err(e),
-        },
-        Err(e) => Err(Box::new(e)),
-    }
+        let lns_thread = scope.spawn(move || {
+            pool.get()
+                .map_err(LatinDbError::from)
+                .and_then(|conn| lns_query(term, &conn))
+        });
+
+        SourceResults {
+            vocab: threads
+                .into_iter()
+                .map(|handle| handle.join().expect("Lookup thread panicked"))
+                .collect(),
+            lns: lns_thread.join().expect("Lookup thread panicked"),
+        }
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{get_connection_pool, query_asvocab};
+    use super::{get_connection_pool, vocab_source, QueryOptions};
 
     #[test]
     fn it_connects() {
@@ -304,10 +196,11 @@ mod tests {
     fn test_asvocab() {
         let pl = get_connection_pool();
         let conn = pl.get().expect("Could not get database connection");
-        let res = query_asvocab("servos", &conn);
+        let asvocab = vocab_source("asvocab").unwrap();
+        let res = asvocab.lookup_form("servos", &QueryOptions::default(), &conn);
         match res {
-            Ok(r) => println!("{}", r),
-            Err(e) => eprintln!("{}", e)
+            Ok(r) => println!("{:?}", r),
+            Err(e) => eprintln!("{}", e),
         }
     }
 }
@@ -0,0 +1,244 @@
+use crate::error::LatinDbError;
+use crate::lns_store::{cache_entries, EntryKey};
+use crate::normalize::{fold_sql, normalize};
+use crate::parsing::LnsEntry;
+use crate::search::lns_cache_stats;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::sql_types::{Array, Text};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+/// A map that holds at most `capacity` values, dropping the one used least
+/// recently to make room.
+struct Lru<V> {
+    capacity: usize,
+    values: HashMap<String, (V, u64)>,
+    /// Keys by the tick they were last used at, oldest first.
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl<V: Clone> Lru<V> {
+    fn new(capacity: usize) -> Self {
+        Lru {
+            capacity,
+            values: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    /// The value for `key`, which becomes the most recently used.
+    fn get(&mut self, key: &str) -> Option<V> {
+        let tick = self.next_tick();
+        let (value, used) = self.values.get_mut(key)?;
+        self.recency.remove(used);
+        *used = tick;
+        self.recency.insert(tick, key.to_string());
+        Some(value.clone())
+    }
+
+    fn insert(&mut self, key: &str, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        let tick = self.next_tick();
+        if let Some((_, used)) = self.values.insert(key.to_string(), (value, tick)) {
+            self.recency.remove(&used);
+        }
+        self.recency.insert(tick, key.to_string());
+        while self.values.len() > self.capacity {
+            match self.recency.pop_first() {
+                Some((_, oldest)) => self.values.remove(&oldest),
+                None => break,
+            };
+        }
+    }
+}
+
+/// How well the LNS cache is doing.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Lookups answered from memory.
+    pub hits: u64,
+    /// Lookups not answered from memory, which went on to the
+    /// `lns_entries` table or the XML files.
+    pub misses: u64,
+    pub len: usize,
+    pub capacity: usize,
+}
+
+/// Parsed LNS entries by key, shared between threads.
+pub struct LnsCache {
+    entries: Mutex<Lru<LnsEntry>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl LnsCache {
+    pub fn new(capacity: usize) -> Self {
+        LnsCache {
+            entries: Mutex::new(Lru::new(capacity)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// The entry for `key`, counting the lookup as a hit or a miss.
+    pub fn get(&self, key: &str) -> Option<LnsEntry> {
+        let found = self.lock().get(key);
+        let counter = if found.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        found
+    }
+
+    /// The entry for `key`, marked as just used, without counting a lookup.
+    pub fn touch(&self, key: &str) -> Option<LnsEntry> {
+        self.lock().get(key)
+    }
+
+    pub fn insert(&self, key: &str, entry: LnsEntry) {
+        self.lock().insert(key, entry);
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let entries = self.lock();
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            len: entries.values.len(),
+            capacity: entries.capacity,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Lru<LnsEntry>> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The words of a frequency list, most frequent first.
+///
+/// Each line is a word, optionally with a rank before it and a count after
+/// it, separated by spaces, tabs or commas. Blank lines and lines starting
+/// with `#` are ignored.
+pub fn parse_frequency_list(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            line.split(|c: char| c.is_whitespace() || c == ',')
+                .find(|field| field.chars().any(char::is_alphabetic))
+                .map(str::to_string)
+        })
+        .collect()
+}
+
+#[derive(QueryableByName, Debug)]
+struct WarmRow {
+    #[sql_type = "Text"]
+    form: String,
+    #[sql_type = "Text"]
+    xml_id: String,
+    #[sql_type = "Text"]
+    key: String,
+}
+
+/// Load the LNS entries for the most frequent `words` into the cache, as
+/// many as it holds, so the first lookups of common words are already hits.
+/// The entries are read as `lns_store::load_entries` reads them, from
+/// `lns_entries` where they have been imported and from the XML files
+/// where they have not. Warming is not counted in the hit and miss figures.
+///
+/// Returns how many entries are cached for the words.
+pub fn warm_lns_cache(words: &[String], connection: &PgConnection) -> Result<usize, LatinDbError> {
+    let mut rank: HashMap<String, usize> = HashMap::new();
+    for word in words {
+        let next = rank.len();
+        rank.entry(normalize(word)).or_insert(next);
+    }
+    let forms: Vec<&String> = rank.keys().collect();
+    let sql = format!(
+        "SELECT DISTINCT {form} AS form, k.xml_id, k.key FROM lns_entry_keys m \
+         JOIN lewis_short_lemmata l ON m.head = l.headword \
+         JOIN lns_entry_keys k ON k.simple_key = m.simple_key WHERE {form} = ANY($1)",
+        form = fold_sql("l.form")
+    );
+    let mut rows: Vec<WarmRow> = diesel::sql_query(sql)
+        .bind::<Array<Text>, _>(&forms)
+        .load(connection)?;
+    rows.sort_by(|a, b| (rank.get(&a.form), &a.key).cmp(&(rank.get(&b.form), &b.key)));
+
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut keys: Vec<EntryKey> = Vec::new();
+    for row in rows {
+        if seen.insert(row.key.clone()) {
+            keys.push(EntryKey {
+                xml_id: row.xml_id,
+                key: row.key,
+            });
+        }
+    }
+    keys.truncate(lns_cache_stats().capacity);
+    // The most frequent go in last, so they are the last to be evicted.
+    keys.reverse();
+    cache_entries(&keys, connection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(head: &str) -> LnsEntry {
+        LnsEntry {
+            head: head.to_string(),
+            orth_orig: head.to_string(),
+            early_i_tags: Vec::new(),
+            senses: Vec::new(),
+            xml_id: None,
+            homograph: None,
+        }
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let cache = LnsCache::new(2);
+        cache.insert("sum", entry("sum"));
+        cache.insert("et", entry("et"));
+        assert_eq!(cache.get("sum"), Some(entry("sum")));
+        cache.insert("in", entry("in"));
+        assert_eq!(cache.get("et"), None);
+        assert_eq!(cache.touch("sum"), Some(entry("sum")));
+        assert_eq!(cache.touch("in"), Some(entry("in")));
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                len: 2,
+                capacity: 2
+            }
+        );
+
+        let off = LnsCache::new(0);
+        off.insert("sum", entry("sum"));
+        assert_eq!(off.stats().len, 0);
+    }
+
+    #[test]
+    fn test_parse_frequency_list() {
+        let text = "# rank word count\n1\tsum\t5000\n2 et 4000\n\nin,3000\n";
+        assert_eq!(parse_frequency_list(text), vec!["sum", "et", "in"]);
+    }
+}
@@ -0,0 +1,265 @@
+use crate::error::LatinDbError;
+use crate::search::XML_FILES;
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// The name of the index file, kept alongside the XML files it indexes.
+pub const INDEX_FILE: &str = "lns.idx";
+
+/// Where an entry's line sits in its XML file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub file: String,
+    pub offset: u64,
+    /// The length of the line in bytes, without its line break.
+    pub length: u64,
+}
+
+/// A key → position index over the Lewis & Short XML files, so an entry
+/// can be read with one seek instead of a scan of its letter file.
+///
+/// On disk it is one tab-separated `key file offset length` line per entry.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LnsIndex {
+    entries: HashMap<String, IndexEntry>,
+}
+
+/// The first `name` attribute on an entry line, if it has one.
+pub(crate) fn entry_attribute<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let pattern = format!(" {}=\"", name);
+    let start = line.find(&pattern)? + pattern.len();
+    let end = line[start..].find('"')?;
+    Some(&line[start..start + end])
+}
+
+/// The `key` attribute of an entry line, if it has one.
+fn entry_key(line: &str) -> Option<&str> {
+    entry_attribute(line, "key")
+}
+
+impl LnsIndex {
+    /// Index every entry in the XML files under `dir`.
+    pub fn build(dir: &Path) -> Result<Self, LatinDbError> {
+        let mut index = LnsIndex::default();
+        let files: BTreeSet<&str> = XML_FILES.values().copied().collect();
+        for file in files {
+            let path = dir.join(file);
+            let reader = match File::open(&path) {
+                Ok(f) => BufReader::new(f),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    return Err(LatinDbError::LnsFileMissing(path))
+                }
+                Err(e) => return Err(LatinDbError::Io(e)),
+            };
+            index.add_file(file, reader)?;
+        }
+        Ok(index)
+    }
+
+    fn add_file<R: BufRead>(&mut self, file: &str, mut reader: R) -> Result<(), LatinDbError> {
+        let mut offset = 0u64;
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let read = reader.read_until(b'\n', &mut line)?;
+            if read == 0 {
+                break;
+            }
+            // Lengths count the bytes as read, so that a stray non-UTF-8
+            // byte does not throw the index off.
+            let mut length = line.len();
+            while length > 0 && matches!(line[length - 1], b'\n' | b'\r') {
+                length -= 1;
+            }
+            let text = String::from_utf8_lossy(&line[..length]);
+            if let Some(key) = entry_key(&text) {
+                // The first entry with a key wins, as it does for a scan.
+                self.entries
+                    .entry(key.to_string())
+                    .or_insert_with(|| IndexEntry {
+                        file: file.to_string(),
+                        offset,
+                        length: length as u64,
+                    });
+            }
+            offset += read as u64;
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&IndexEntry> {
+        self.entries.get(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), LatinDbError> {
+        let mut out = BufWriter::new(File::create(path)?);
+        let mut keys: Vec<&String> = self.entries.keys().collect();
+        keys.sort();
+        for key in keys {
+            let entry = &self.entries[key];
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{}",
+                key, entry.file, entry.offset, entry.length
+            )?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    /// The index at `path`, or `None` if there is no file there. An index
+    /// that exists but cannot be read or parsed is an error.
+    pub fn load_if_present(path: &Path) -> Result<Option<Self>, LatinDbError> {
+        match Self::load(path) {
+            Ok(index) => Ok(Some(index)),
+            Err(LatinDbError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, LatinDbError> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut index = LnsIndex::default();
+        for line in reader.lines() {
+            let line = line?;
+            let fields: Vec<&str> = line.split('\t').collect();
+            let parsed = match fields.as_slice() {
+                [key, file, offset, length] => offset
+                    .parse()
+                    .ok()
+                    .zip(length.parse().ok())
+                    .map(|(offset, length)| (key, file, offset, length)),
+                _ => None,
+            };
+            let (key, file, offset, length) = parsed.ok_or_else(|| {
+                LatinDbError::Config(format!("malformed LNS index line: {:?}", line))
+            })?;
+            index.entries.insert(
+                key.to_string(),
+                IndexEntry {
+                    file: file.to_string(),
+                    offset,
+                    length,
+                },
+            );
+        }
+        Ok(index)
+    }
+
+    /// Read the line for `key` from the XML files under `dir`.
+    ///
+    /// Returns `Ok(None)` if the key is not indexed, or if the line found
+    /// at the recorded offset is not that entry, as happens when the XML has
+    /// changed since the index was built. Callers should fall back to a scan.
+    pub fn read_entry(&self, dir: &Path, key: &str) -> Result<Option<String>, LatinDbError> {
+        let entry = match self.get(key) {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        let path = dir.join(&entry.file);
+        let mut file = match File::open(&path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(LatinDbError::LnsFileMissing(path))
+            }
+            Err(e) => return Err(LatinDbError::Io(e)),
+        };
+        file.seek(SeekFrom::Start(entry.offset))?;
+        let mut buffer = Vec::with_capacity(entry.length as usize);
+        file.take(entry.length).read_to_end(&mut buffer)?;
+        let text = String::from_utf8_lossy(&buffer);
+        let line = text.trim();
+        let whole_line = line.starts_with('<') && line.ends_with('>') && !line.contains('\n');
+        if whole_line && entry_key(line) == Some(key) {
+            Ok(Some(line.to_string()))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XML: &str = "<TEI.2><text><body><div0 type=\"alphabetic letter\" n=\"d\">\n\
+        <div2 id=\"n1\" key=\"do^mi^nus\" type=\"main\"><head>dŏmĭnus</head></div2>\n\
+        <div2 id=\"n2\" key=\"dominus\" type=\"main\"><head>dominus</head></div2>\n\
+        </div0></body></text></TEI.2>\n";
+
+    #[test]
+    fn test_entry_key() {
+        assert_eq!(
+            entry_key("<div2 id=\"n1\" key=\"do^mi^nus\" type=\"main\">"),
+            Some("do^mi^nus")
+        );
+        assert_eq!(entry_key("</div0>"), None);
+    }
+
+    #[test]
+    fn test_index_round_trip_and_seek() {
+        let dir = std::env::temp_dir().join(format!("lns_index_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("d.xml"), XML).unwrap();
+
+        let mut index = LnsIndex::default();
+        index.add_file("d.xml", XML.as_bytes()).unwrap();
+        assert_eq!(index.len(), 2);
+        let path = dir.join(INDEX_FILE);
+        index.write(&path).unwrap();
+        let loaded = LnsIndex::load(&path).unwrap();
+        assert_eq!(loaded, index);
+
+        let line = loaded.read_entry(&dir, "dominus").unwrap().unwrap();
+        assert!(line.starts_with("<div2 id=\"n2\""));
+        assert!(line.ends_with("</div2>"));
+        assert_eq!(loaded.read_entry(&dir, "servus").unwrap(), None);
+
+        // An entry moved since indexing is not misread.
+        std::fs::write(dir.join("d.xml"), format!("\n{}", XML)).unwrap();
+        assert_eq!(loaded.read_entry(&dir, "dominus").unwrap(), None);
+
+        // Indented entries are found at their recorded offset.
+        let indented = XML.replace("\n<div2", "\n  <div2");
+        std::fs::write(dir.join("d.xml"), &indented).unwrap();
+        let mut index = LnsIndex::default();
+        index.add_file("d.xml", indented.as_bytes()).unwrap();
+        let line = index.read_entry(&dir, "dominus").unwrap().unwrap();
+        assert!(line.starts_with("<div2 id=\"n2\""));
+
+        // A byte that is not UTF-8 counts as one byte.
+        let latin1: Vec<u8> = XML
+            .replace("dŏmĭnus", "d?minus")
+            .bytes()
+            .map(|b| if b == b'?' { 0xe9 } else { b })
+            .collect();
+        std::fs::write(dir.join("d.xml"), &latin1).unwrap();
+        let mut index = LnsIndex::default();
+        index.add_file("d.xml", latin1.as_slice()).unwrap();
+        let first = index.get("do^mi^nus").unwrap();
+        assert_eq!(
+            index.get("dominus").unwrap().offset,
+            first.offset + first.length + 1
+        );
+        let line = index.read_entry(&dir, "dominus").unwrap().unwrap();
+        assert!(line.starts_with("<div2 id=\"n2\""));
+
+        assert_eq!(
+            LnsIndex::load_if_present(&dir.join("missing.tsv")).unwrap(),
+            None
+        );
+        std::fs::write(&path, "dominus\td.xml\n").unwrap();
+        assert!(LnsIndex::load_if_present(&path).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
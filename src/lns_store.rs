@@ -0,0 +1,273 @@
+use crate::error::LatinDbError;
+use crate::lns_index::entry_attribute;
+use crate::models::{NewStoredLnsEntry, StoredLnsEntry};
+use crate::parsing::{homograph_number, parse_entry, LnsEntry};
+use crate::schema::lns_entries;
+use crate::search::{
+    cache_lns_entry, cached_lns_entry, read_and_cache_lns_entry, touch_lns_entry, XML_FILES,
+};
+use diesel::pg::upsert::excluded;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Rows inserted per statement, well under Postgres' limit on bind
+/// parameters.
+const BATCH_SIZE: usize = 1000;
+
+/// An `lns_entry_keys` row's way into the entries: the `xml_id` to read it
+/// from the table by, and the `key` to find it in the XML files by.
+#[derive(Queryable, Debug, Clone, PartialEq, Eq)]
+pub struct EntryKey {
+    pub xml_id: String,
+    pub key: String,
+}
+
+/// What `import_lns` did.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ImportSummary {
+    pub imported: usize,
+    /// The keys of entries that could not be parsed and were left out.
+    pub skipped: Vec<String>,
+}
+
+/// Parse the entry lines of an XML file into rows. Lines without both an
+/// `id` and a `key` are not entries; entries that do not parse are skipped.
+fn read_entries<R: BufRead>(
+    reader: R,
+    now: SystemTime,
+    summary: &mut ImportSummary,
+) -> Result<Vec<NewStoredLnsEntry>, LatinDbError> {
+    let mut rows = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim_end();
+        let (xml_id, key) = match (entry_attribute(line, "id"), entry_attribute(line, "key")) {
+            (Some(xml_id), Some(key)) => (xml_id, key),
+            _ => continue,
+        };
+        let entry = match parse_entry(line) {
+            Ok((_, entry)) => LnsEntry::from(entry),
+            Err(_) => {
+                summary.skipped.push(key.to_string());
+                continue;
+            }
+        };
+        rows.push(NewStoredLnsEntry {
+            xml_id: xml_id.to_string(),
+            key: key.to_string(),
+            head: entry.head,
+            orth_orig: entry.orth_orig,
+            early_i_tags: serde_json::to_value(&entry.early_i_tags)?,
+            senses: serde_json::to_value(&entry.senses)?,
+            raw_xml: line.to_string(),
+            updated_at: now,
+        });
+    }
+    Ok(rows)
+}
+
+/// Store every entry in the XML files under `dir` in `lns_entries`, one
+/// transaction per file. Entries already imported are updated in place,
+/// so this can be rerun after the XML files change.
+pub fn import_lns(dir: &Path, connection: &PgConnection) -> Result<ImportSummary, LatinDbError> {
+    use crate::schema::lns_entries::dsl::*;
+
+    let mut summary = ImportSummary::default();
+    let files: BTreeSet<&str> = XML_FILES.values().copied().collect();
+    for file in files {
+        let path = dir.join(file);
+        let reader = match File::open(&path) {
+            Ok(f) => BufReader::new(f),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(LatinDbError::LnsFileMissing(path))
+            }
+            Err(e) => return Err(LatinDbError::Io(e)),
+        };
+        let rows = read_entries(reader, SystemTime::now(), &mut summary)?;
+        connection.transaction::<_, LatinDbError, _>(|| {
+            for batch in rows.chunks(BATCH_SIZE) {
+                diesel::insert_into(lns_entries)
+                    .values(batch)
+                    .on_conflict(xml_id)
+                    .do_update()
+                    .set((
+                        key.eq(excluded(key)),
+                        head.eq(excluded(head)),
+                        orth_orig.eq(excluded(orth_orig)),
+                        early_i_tags.eq(excluded(early_i_tags)),
+                        senses.eq(excluded(senses)),
+                        raw_xml.eq(excluded(raw_xml)),
+                        updated_at.eq(excluded(updated_at)),
+                    ))
+                    .execute(connection)?;
+            }
+            Ok(())
+        })?;
+        summary.imported += rows.len();
+    }
+    Ok(summary)
+}
+
+impl TryFrom<StoredLnsEntry> for LnsEntry {
+    type Error = LatinDbError;
+
+    fn try_from(row: StoredLnsEntry) -> Result<Self, Self::Error> {
+        Ok(LnsEntry {
+            head: row.head,
+            orth_orig: row.orth_orig,
+            early_i_tags: serde_json::from_value(row.early_i_tags)?,
+            senses: serde_json::from_value(row.senses)?,
+            homograph: homograph_number(&row.key),
+            xml_id: Some(row.xml_id),
+        })
+    }
+}
+
+/// Whether any entries have been imported into `lns_entries`.
+pub fn entries_imported(connection: &PgConnection) -> Result<bool, LatinDbError> {
+    use diesel::dsl::exists;
+
+    Ok(
+        diesel::select(exists(lns_entries::table.select(lns_entries::id)))
+            .get_result(connection)?,
+    )
+}
+
+/// The entries for `keys`, in order: read from `lns_entries` where they
+/// have been imported, and from the XML files where they have not.
+pub fn load_entries(
+    keys: &[EntryKey],
+    connection: &PgConnection,
+) -> Result<Vec<LnsEntry>, LatinDbError> {
+    let mut entries = Vec::new();
+    for found in load_entries_by_key(keys, connection)? {
+        entries.extend(found?);
+    }
+    Ok(entries)
+}
+
+/// As `load_entries`, but keeping each key's entries apart, so that a key
+/// whose XML file cannot be read does not lose the others. Only a failure
+/// of the query against `lns_entries` fails the whole call.
+///
+/// Entries already in the LNS cache are taken from it; the rest are read
+/// in one query and cached, and only those not imported are read from the
+/// XML files. Each key counts once towards the cache's hits or misses.
+pub fn load_entries_by_key(
+    keys: &[EntryKey],
+    connection: &PgConnection,
+) -> Result<Vec<Result<Vec<LnsEntry>, LatinDbError>>, LatinDbError> {
+    load_through_cache(keys, cached_lns_entry, connection)
+}
+
+/// Make sure the entries for `keys` are cached, reading any that are not
+/// as `load_entries` does, without counting hits or misses. Returns how
+/// many of them are cached.
+pub(crate) fn cache_entries(
+    keys: &[EntryKey],
+    connection: &PgConnection,
+) -> Result<usize, LatinDbError> {
+    let mut cached = 0;
+    for found in load_through_cache(keys, touch_lns_entry, connection)? {
+        if !found?.is_empty() {
+            cached += 1;
+        }
+    }
+    Ok(cached)
+}
+
+/// `load_entries_by_key`, taking the entries already cached from `cached`.
+fn load_through_cache<F>(
+    keys: &[EntryKey],
+    cached: F,
+    connection: &PgConnection,
+) -> Result<Vec<Result<Vec<LnsEntry>, LatinDbError>>, LatinDbError>
+where
+    F: Fn(&str) -> Option<LnsEntry>,
+{
+    let cached: Vec<Option<LnsEntry>> = keys.iter().map(|k| cached(&k.key)).collect();
+    let ids: Vec<&str> = keys
+        .iter()
+        .zip(&cached)
+        .filter(|(_, entry)| entry.is_none())
+        .map(|(k, _)| k.xml_id.as_str())
+        .collect();
+    let mut stored: HashMap<String, StoredLnsEntry> = if ids.is_empty() {
+        HashMap::new()
+    } else {
+        lns_entries::table
+            .filter(lns_entries::xml_id.eq_any(&ids))
+            .load::<StoredLnsEntry>(connection)?
+            .into_iter()
+            .map(|row| (row.xml_id.clone(), row))
+            .collect()
+    };
+
+    Ok(keys
+        .iter()
+        .zip(cached)
+        .map(|(entry_key, cached)| {
+            if let Some(entry) = cached {
+                return Ok(vec![entry]);
+            }
+            match stored.remove(&entry_key.xml_id) {
+                Some(row) => {
+                    let entry = LnsEntry::try_from(row)?;
+                    cache_lns_entry(&entry_key.key, entry.clone());
+                    Ok(vec![entry])
+                }
+                None => Ok(read_and_cache_lns_entry(&entry_key.key)?
+                    .into_iter()
+                    .collect()),
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XML: &str = "<TEI.2><text><body><div0 type=\"alphabetic letter\" n=\"d\">\n\
+        <div2 id=\"n14286\" key=\"dominus\" type=\"main\"><head extent=\"full\" lang=\"la\" \
+        opt=\"n\" orth_orig=\"dominus\">dominus</head>, i, m. <sense id=\"n14286.1\" n=\"I\" \
+        level=\"1\"><i>lord, master, sir</i> <author>Ter.</author></sense></div2>\n\
+        <div2 id=\"n14287\" key=\"domo\" type=\"main\"><head>domo</head></div2>\n\
+        </div0></body></text></TEI.2>\n";
+
+    #[test]
+    fn test_read_entries() {
+        let mut summary = ImportSummary::default();
+        let rows = read_entries(XML.as_bytes(), SystemTime::now(), &mut summary).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].xml_id, "n14286");
+        assert_eq!(rows[0].key, "dominus");
+        assert_eq!(rows[0].head, "dominus");
+        assert!(rows[0].raw_xml.starts_with("<div2 id=\"n14286\""));
+        assert_eq!(summary.skipped, vec!["domo".to_string()]);
+
+        let stored = StoredLnsEntry {
+            id: 1,
+            xml_id: rows[0].xml_id.clone(),
+            key: rows[0].key.clone(),
+            head: rows[0].head.clone(),
+            orth_orig: rows[0].orth_orig.clone(),
+            early_i_tags: rows[0].early_i_tags.clone(),
+            senses: rows[0].senses.clone(),
+            raw_xml: rows[0].raw_xml.clone(),
+            created_at: rows[0].updated_at,
+            updated_at: rows[0].updated_at,
+        };
+        let (_, parsed) = parse_entry(&rows[0].raw_xml).unwrap();
+        let expected = LnsEntry {
+            xml_id: Some("n14286".to_string()),
+            ..LnsEntry::from(parsed)
+        };
+        assert_eq!(LnsEntry::try_from(stored).unwrap(), expected);
+    }
+}
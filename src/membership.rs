@@ -0,0 +1,120 @@
+use crate::error::LatinDbError;
+use crate::normalize::{fold_sql, search_key};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::sql_types::{Array, Bool, Text};
+use serde::Serialize;
+
+/// Which word lists contain a headword.
+#[derive(QueryableByName, Serialize, Debug, Clone, PartialEq)]
+pub struct ListMembership {
+    #[sql_type = "Text"]
+    pub headword: String,
+    /// On `gcse_latin`, or flagged `gcsevocab` in `asvocab2`.
+    #[sql_type = "Bool"]
+    pub gcse: bool,
+    /// On `asvocab`, or flagged `asvocab` in `asvocab2`.
+    #[sql_type = "Bool"]
+    pub asvocab: bool,
+    #[sql_type = "Bool"]
+    pub clc: bool,
+    /// In Whitaker's Words.
+    #[sql_type = "Bool"]
+    pub wwords: bool,
+    /// Has an entry in Lewis and Short.
+    #[sql_type = "Bool"]
+    pub lns: bool,
+}
+
+impl ListMembership {
+    /// Whether the list named as in the fields above contains the headword.
+    pub fn contains(&self, list: &str) -> bool {
+        match list {
+            "gcse" => self.gcse,
+            "asvocab" => self.asvocab,
+            "clc" => self.clc,
+            "wwords" => self.wwords,
+            "lns" => self.lns,
+            _ => false,
+        }
+    }
+}
+
+/// The membership columns for every headword in the CTE `heads`.
+fn membership_sql(heads: &str) -> String {
+    format!(
+        "WITH heads AS ({}) \
+         SELECT h.headword, \
+         EXISTS (SELECT 1 FROM gcse_latin g WHERE g.headword = h.headword) \
+           OR EXISTS (SELECT 1 FROM asvocab2 a2 WHERE a2.headword = h.headword AND a2.gcsevocab) AS gcse, \
+         EXISTS (SELECT 1 FROM asvocab a WHERE a.headword = h.headword) \
+           OR EXISTS (SELECT 1 FROM asvocab2 a2 WHERE a2.headword = h.headword AND a2.asvocab) AS asvocab, \
+         EXISTS (SELECT 1 FROM clc4 c WHERE c.headword = h.headword) AS clc, \
+         EXISTS (SELECT 1 FROM wwords w WHERE w.headword = h.headword) AS wwords, \
+         EXISTS (SELECT 1 FROM lns_entry_keys k WHERE k.head = h.headword) AS lns \
+         FROM heads h ORDER BY h.headword",
+        heads
+    )
+}
+
+/// Which lists contain `headword`, matched exactly.
+pub fn list_membership(
+    headword: &str,
+    connection: &PgConnection,
+) -> Result<ListMembership, LatinDbError> {
+    let membership = diesel::sql_query(membership_sql("SELECT $1::varchar AS headword"))
+        .bind::<Text, _>(headword)
+        .get_result(connection)?;
+    Ok(membership)
+}
+
+/// List membership for each of `headwords`, matched exactly, in headword
+/// order.
+pub fn list_memberships(
+    headwords: &[String],
+    connection: &PgConnection,
+) -> Result<Vec<ListMembership>, LatinDbError> {
+    let memberships = diesel::sql_query(membership_sql(
+        "SELECT DISTINCT unnest($1::varchar[]) AS headword",
+    ))
+    .bind::<Array<Text>, _>(headwords)
+    .load(connection)?;
+    Ok(memberships)
+}
+
+/// List membership for each headword `term` is a form of, by headword.
+/// `term` is normalized first unless `exact` is set.
+pub fn form_membership(
+    term: &str,
+    exact: bool,
+    connection: &PgConnection,
+) -> Result<Vec<ListMembership>, LatinDbError> {
+    let form = if exact {
+        "form".to_string()
+    } else {
+        fold_sql("form")
+    };
+    let heads = format!(
+        "SELECT DISTINCT headword FROM lewis_short_lemmata WHERE {} = $1",
+        form
+    );
+    let memberships = diesel::sql_query(membership_sql(&heads))
+        .bind::<Text, _>(search_key(term, exact))
+        .load(connection)?;
+    Ok(memberships)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_membership_sql_uses_asvocab2_flags() {
+        let sql = membership_sql("SELECT 'servus' AS headword");
+        assert!(sql.contains("a2.gcsevocab"));
+        assert!(sql.contains("a2.asvocab"));
+        for column in ["gcse", "asvocab", "clc", "wwords", "lns"] {
+            assert!(sql.contains(&format!("AS {}", column)));
+        }
+    }
+}
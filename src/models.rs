@@ -1,4 +1,11 @@
+use super::analysis::Analysis;
+use super::enclitics::Compound;
+use super::error::LatinDbError;
+use super::fuzzy::FuzzyMatch;
+use super::parsing::LnsEntry;
 use super::schema::*;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
 use std::time::SystemTime;
 #[derive(Queryable, Insertable, PartialEq, Debug)]
 #[table_name = "clc4"]
@@ -11,6 +18,19 @@ pub struct Clc4 {
     pub updated_at: SystemTime,
 }
 
+/// A row of `asvocab2`, the combined AS list that flags which words are
+/// also on the GCSE list.
+#[derive(Queryable, PartialEq, Debug)]
+pub struct Asvocab2 {
+    pub id: i64,
+    pub headword: Option<String>,
+    pub dict_form: Option<String>,
+    pub part_of_speech: Option<String>,
+    pub meaning: Option<String>,
+    pub asvocab: Option<bool>,
+    pub gcsevocab: Option<bool>,
+}
+
 #[derive(Queryable, Insertable, PartialEq, Debug)]
 #[table_name = "lewis_short_lemmata"]
 pub struct Lemma {
@@ -18,3 +38,304 @@ pub struct Lemma {
     pub form: String,
     pub analysis: String,
 }
+
+impl Lemma {
+    pub fn parsed_analysis(&self) -> Analysis {
+        Analysis::parse(&self.analysis)
+    }
+}
+
+/// Someone whose progress through the word lists is being tracked.
+#[derive(Queryable, Serialize, PartialEq, Debug, Clone)]
+pub struct Learner {
+    pub id: i64,
+    pub name: String,
+    pub created_at: SystemTime,
+    pub updated_at: SystemTime,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "learners"]
+pub struct NewLearner<'a> {
+    pub name: &'a str,
+}
+
+/// A learner's spaced-repetition state for one word on one list.
+#[derive(Queryable, Serialize, PartialEq, Debug, Clone)]
+pub struct CardState {
+    pub id: i64,
+    pub learner_id: i64,
+    pub list: String,
+    pub entry_id: i64,
+    pub repetitions: i32,
+    pub interval_days: i32,
+    pub ease_factor: f64,
+    pub due_at: SystemTime,
+    pub created_at: SystemTime,
+    pub updated_at: SystemTime,
+}
+
+#[derive(Insertable, AsChangeset, Debug)]
+#[table_name = "card_states"]
+pub struct NewCardState<'a> {
+    pub learner_id: i64,
+    pub list: &'a str,
+    pub entry_id: i64,
+    pub repetitions: i32,
+    pub interval_days: i32,
+    pub ease_factor: f64,
+    pub due_at: SystemTime,
+    pub updated_at: SystemTime,
+}
+
+/// One answer a learner gave, graded 0 (forgotten) to 5 (perfect).
+#[derive(Queryable, Serialize, PartialEq, Debug, Clone)]
+pub struct ReviewRecord {
+    pub id: i64,
+    pub learner_id: i64,
+    pub list: String,
+    pub entry_id: i64,
+    pub grade: i16,
+    pub reviewed_at: SystemTime,
+    pub created_at: SystemTime,
+    pub updated_at: SystemTime,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "review_records"]
+pub struct NewReviewRecord<'a> {
+    pub learner_id: i64,
+    pub list: &'a str,
+    pub entry_id: i64,
+    pub grade: i16,
+    pub reviewed_at: SystemTime,
+}
+
+/// A Lewis & Short entry stored by `lns_store::import_lns`.
+#[derive(Queryable, PartialEq, Debug)]
+pub struct StoredLnsEntry {
+    pub id: i64,
+    pub xml_id: String,
+    pub key: String,
+    pub head: String,
+    pub orth_orig: String,
+    pub early_i_tags: serde_json::Value,
+    pub senses: serde_json::Value,
+    pub raw_xml: String,
+    pub created_at: SystemTime,
+    pub updated_at: SystemTime,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "lns_entries"]
+pub struct NewStoredLnsEntry {
+    pub xml_id: String,
+    pub key: String,
+    pub head: String,
+    pub orth_orig: String,
+    pub early_i_tags: serde_json::Value,
+    pub senses: serde_json::Value,
+    pub raw_xml: String,
+    pub updated_at: SystemTime,
+}
+
+/// A single row from one of the vocabulary tables.
+///
+/// `clc4` has no `part_of_speech` column and only `wwords` has a `class`,
+/// so those fields are optional and left out of the JSON when absent.
+#[derive(Serialize, PartialEq, Debug, Clone)]
+pub struct VocabHit {
+    pub id: i64,
+    pub headword: String,
+    pub dict_form: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub part_of_speech: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class: Option<String>,
+    pub meaning: String,
+    /// The readings of the looked-up form that led to this row. Only the
+    /// form-based lookups fill this in.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub analyses: Vec<Analysis>,
+}
+
+impl VocabHit {
+    /// Attach a raw `lewis_short_lemmata.analysis` string to this hit.
+    pub fn with_analysis(mut self, analysis: &str) -> Self {
+        self.analyses.push(Analysis::parse(analysis));
+        self
+    }
+}
+
+/// `(id, headword, dict_form, part_of_speech, meaning)` as selected from
+/// `gcse_latin` and `asvocab`.
+pub type ListRow = (i64, String, String, String, String);
+/// `(id, headword, dict_form, meaning)` as selected from `clc4`.
+pub type ClcRow = (i64, String, String, String);
+/// `(id, headword, dict_form, part_of_speech, class, meaning)` as selected from `wwords`.
+pub type WwordsRow = (i64, String, String, String, Option<String>, String);
+
+impl From<ListRow> for VocabHit {
+    fn from((id, headword, dict_form, part_of_speech, meaning): ListRow) -> Self {
+        VocabHit {
+            id,
+            headword,
+            dict_form,
+            part_of_speech: Some(part_of_speech),
+            class: None,
+            meaning,
+            analyses: Vec::new(),
+        }
+    }
+}
+
+impl From<ClcRow> for VocabHit {
+    fn from((id, headword, dict_form, meaning): ClcRow) -> Self {
+        VocabHit {
+            id,
+            headword,
+            dict_form,
+            part_of_speech: None,
+            class: None,
+            meaning,
+            analyses: Vec::new(),
+        }
+    }
+}
+
+impl From<WwordsRow> for VocabHit {
+    fn from((id, headword, dict_form, part_of_speech, class, meaning): WwordsRow) -> Self {
+        VocabHit {
+            id,
+            headword,
+            dict_form,
+            part_of_speech: Some(part_of_speech),
+            class,
+            meaning,
+            analyses: Vec::new(),
+        }
+    }
+}
+
+/// Collapse hits from one source that refer to the same row.
+///
+/// A form with several analyses of one headword (`servis`, dat. and abl.
+/// pl.) joins to each vocabulary row once per analysis; this keeps one hit
+/// per row `id` with all of its analyses, ordered by headword, then
+/// dict_form.
+pub fn group_hits(hits: Vec<VocabHit>) -> Vec<VocabHit> {
+    let mut grouped: Vec<VocabHit> = Vec::new();
+    let mut positions: HashMap<i64, usize> = HashMap::new();
+    for hit in hits {
+        match positions.get(&hit.id) {
+            Some(&index) => {
+                let existing = &mut grouped[index];
+                for analysis in hit.analyses {
+                    if !existing.analyses.contains(&analysis) {
+                        existing.analyses.push(analysis);
+                    }
+                }
+            }
+            None => {
+                positions.insert(hit.id, grouped.len());
+                grouped.push(hit);
+            }
+        }
+    }
+    grouped
+        .sort_by(|a, b| (&a.headword, &a.dict_form, a.id).cmp(&(&b.headword, &b.dict_form, b.id)));
+    grouped
+}
+
+/// Everything the dictionary knows about a term.
+///
+/// `vocab` is keyed by `VocabSource::name()` and flattened into the JSON, so
+/// each source appears as its own top-level array alongside `lns`.
+#[derive(Serialize, PartialEq, Debug, Clone, Default)]
+pub struct LookupResult {
+    #[serde(flatten)]
+    pub vocab: BTreeMap<String, Vec<VocabHit>>,
+    pub lns: Vec<LnsEntry>,
+    /// Set when the term itself matched nothing and these hits are for the
+    /// word left after splitting off an enclitic.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compound: Option<Compound>,
+    /// Near misses, filled in by `lookup_form` when nothing matched.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<FuzzyMatch>,
+    /// Sources that failed, keyed by source name, with the error message.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub errors: BTreeMap<String, String>,
+}
+
+impl LookupResult {
+    /// The hits from the named source, or an empty slice if it returned none.
+    pub fn source(&self, name: &str) -> &[VocabHit] {
+        self.vocab.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Whether no source found anything.
+    pub fn is_empty(&self) -> bool {
+        self.vocab.values().all(Vec::is_empty) && self.lns.is_empty()
+    }
+}
+
+/// The raw outcome of asking each source about a term, before failures are
+/// folded into `LookupResult::errors`.
+#[derive(Debug)]
+pub struct SourceResults {
+    pub vocab: Vec<(&'static str, Result<Vec<VocabHit>, LatinDbError>)>,
+    pub lns: Result<Vec<LnsEntry>, LatinDbError>,
+}
+
+impl From<SourceResults> for LookupResult {
+    fn from(results: SourceResults) -> Self {
+        let mut lookup = LookupResult::default();
+        for (name, hits) in results.vocab {
+            match hits {
+                Ok(hits) => {
+                    lookup.vocab.insert(name.to_string(), hits);
+                }
+                Err(e) => {
+                    lookup.errors.insert(name.to_string(), e.to_string());
+                }
+            }
+        }
+        match results.lns {
+            Ok(entries) => lookup.lns = entries,
+            Err(e) => {
+                lookup.errors.insert("lns".to_string(), e.to_string());
+            }
+        }
+        lookup
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(id: i64, headword: &str, analysis: &str) -> VocabHit {
+        VocabHit::from((
+            id,
+            headword.to_string(),
+            format!("{}, dict", headword),
+            "meaning".to_string(),
+        ))
+        .with_analysis(analysis)
+    }
+
+    #[test]
+    fn test_group_hits() {
+        let grouped = group_hits(vec![
+            hit(2, "servus", "noun masc dat pl"),
+            hit(1, "amo", "verb 1st sg pres ind pass"),
+            hit(2, "servus", "noun masc abl pl"),
+            hit(2, "servus", "noun masc abl pl"),
+        ]);
+        assert_eq!(grouped.len(), 2);
+        assert_eq!(grouped[0].headword, "amo");
+        assert_eq!(grouped[1].headword, "servus");
+        assert_eq!(grouped[1].analyses.len(), 2);
+    }
+}
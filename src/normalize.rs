@@ -0,0 +1,100 @@
+use diesel::expression::{BoxableExpression, NonAggregate};
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use diesel::query_builder::QueryFragment;
+use diesel::sql_types::{Bool, Text};
+
+/// Letters folded onto their plain spelling, paired by position with
+/// `FOLD_TO`: j and v become i and u, and vowels lose macrons, breves and
+/// diaereses. The same pair is written into the `latin_fold` SQL function
+/// the migrations create, so the columns and the search terms are folded
+/// identically.
+const FOLD_FROM: &str = "jvāēīōūȳăĕĭŏŭëï";
+const FOLD_TO: &str = "iuaeiouyaeiouei";
+
+sql_function!(fn latin_fold(x: Text) -> Text);
+
+/// Fold `term` to the spelling lookups compare on: lowercase, with no
+/// macrons or breves, i for j and u for v. "Iuvenis", "juvenis", "iūvenis"
+/// and "ivvenis" all normalize to "iuuenis".
+pub fn normalize(term: &str) -> String {
+    term.trim()
+        .to_lowercase()
+        .chars()
+        // Combining diacritics, as left by decomposed input.
+        .filter(|c| !('\u{0300}'..='\u{036f}').contains(c))
+        .map(|c| match FOLD_FROM.chars().position(|from| from == c) {
+            Some(index) => FOLD_TO.chars().nth(index).unwrap_or(c),
+            None => c,
+        })
+        .collect()
+}
+
+/// The SQL for `column` folded the same way as `normalize`. The folded
+/// columns that lookups filter on are indexed on this expression.
+pub fn fold_sql(column: &str) -> String {
+    format!("latin_fold({})", column)
+}
+
+/// The term to bind against a column: `term` itself for an exact search,
+/// its normalized form otherwise.
+pub fn search_key(term: &str, exact: bool) -> String {
+    if exact {
+        term.to_string()
+    } else {
+        normalize(term)
+    }
+}
+
+/// A filter matching `column` against `term`, exactly or after folding
+/// both sides.
+pub(crate) fn column_matches<QS, C>(
+    column: C,
+    term: &str,
+    exact: bool,
+) -> Box<dyn BoxableExpression<QS, Pg, SqlType = Bool>>
+where
+    C: Expression<SqlType = Text>
+        + SelectableExpression<QS>
+        + NonAggregate
+        + QueryFragment<Pg>
+        + 'static,
+{
+    if exact {
+        Box::new(column.eq(term.to_string()))
+    } else {
+        Box::new(latin_fold(column).eq(normalize(term)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold_tables_line_up() {
+        assert_eq!(FOLD_FROM.chars().count(), FOLD_TO.chars().count());
+    }
+
+    #[test]
+    fn test_fold_function_matches_tables() {
+        let migration = include_str!("../migrations/2026-10-18-000002_create_latin_fold/up.sql");
+        assert!(migration.contains(&format!("'{}'", FOLD_FROM)));
+        assert!(migration.contains(&format!("'{}'", FOLD_TO)));
+    }
+
+    #[test]
+    fn test_normalize_spellings_agree() {
+        for spelling in ["Iuvenis", "juvenis", "iūvenis", "ivvenis", "IVVENIS"] {
+            assert_eq!(normalize(spelling), "iuuenis");
+        }
+        assert_eq!(normalize("do\u{0304}minus"), "dominus");
+        assert_eq!(normalize("poëta"), "poeta");
+    }
+
+    #[test]
+    fn test_search_key_exact() {
+        assert_eq!(search_key("Iuvenis", true), "Iuvenis");
+        assert_eq!(search_key("Iuvenis", false), "iuuenis");
+    }
+}
@@ -0,0 +1,161 @@
+use crate::analysis::{
+    Case, Degree, Gender, Mood, Number, PartOfSpeech, Person, Tense, Voice, PARTS_OF_SPEECH,
+    VERBAL_NOUN_MOODS,
+};
+
+/// Restrictions on the readings a form lookup returns.
+///
+/// Every field left as `None` matches anything, so `QueryOptions::default()`
+/// filters nothing. The filters are applied in the lemmata query itself
+/// (see `filter_sql`): the feature filters against each reading's analysis,
+/// and `part_of_speech` against that and against the source's own
+/// `part_of_speech` column where it has one.
+///
+/// Terms are normalized before matching (see `normalize`) unless `exact`
+/// is set, in which case they must match the stored form character for
+/// character.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct QueryOptions {
+    pub part_of_speech: Option<PartOfSpeech>,
+    pub case: Option<Case>,
+    pub number: Option<Number>,
+    pub gender: Option<Gender>,
+    pub tense: Option<Tense>,
+    pub mood: Option<Mood>,
+    pub voice: Option<Voice>,
+    pub person: Option<Person>,
+    pub degree: Option<Degree>,
+    pub exact: bool,
+}
+
+/// A Postgres regular expression matching any of `tokens` as a whole token
+/// of a lowercased analysis, split much as `Analysis::parse` splits it.
+fn token_regex<'a>(tokens: impl IntoIterator<Item = &'a str>) -> String {
+    let tokens: Vec<&str> = tokens.into_iter().collect();
+    format!("(^|[^a-z0-9_])({})([^a-z0-9_]|$)", tokens.join("|"))
+}
+
+/// The spellings of `value` in a `TOKENS` table.
+fn spellings<T: PartialEq + Copy>(
+    table: &'static [(&'static str, T)],
+    value: T,
+) -> impl Iterator<Item = &'static str> {
+    table
+        .iter()
+        .filter(move |(_, v)| *v == value)
+        .map(|(spelling, _)| *spelling)
+}
+
+fn sql_list<'a>(tokens: impl IntoIterator<Item = &'a str>) -> String {
+    let quoted: Vec<String> = tokens.into_iter().map(|t| format!("'{}'", t)).collect();
+    quoted.join(", ")
+}
+
+impl QueryOptions {
+    /// Whether no filters are set. `exact` is not a filter and is ignored.
+    pub fn is_empty(&self) -> bool {
+        QueryOptions {
+            exact: false,
+            ..self.clone()
+        } == QueryOptions::default()
+    }
+
+    /// A SQL condition that holds for the readings these options allow,
+    /// given the analysis column and, if the source has one, its
+    /// `part_of_speech` column. `TRUE` when no filters are set.
+    ///
+    /// A reading whose analysis names no part of speech counts as a verb if
+    /// it is a participle, gerund, gerundive or supine, and as unknown (so
+    /// allowed by any `part_of_speech`) otherwise. Likewise a source row
+    /// whose part of speech is missing or not recognised is allowed.
+    pub fn filter_sql(&self, analysis: &str, part_of_speech: Option<&str>) -> String {
+        let analysis = format!("lower({})", analysis);
+        let has = |regex: String| format!("{} ~ '{}'", analysis, regex);
+        let lacks = |regex: String| format!("{} !~ '{}'", analysis, regex);
+
+        let mut conditions: Vec<String> = Vec::new();
+        if let Some(wanted) = self.part_of_speech {
+            let own = PARTS_OF_SPEECH
+                .iter()
+                .copied()
+                .filter(|token| PartOfSpeech::from_token(token) == Some(wanted));
+            let unnamed = lacks(token_regex(PARTS_OF_SPEECH));
+            let unknown = if wanted == PartOfSpeech::Verb {
+                unnamed
+            } else {
+                let verbal = VERBAL_NOUN_MOODS
+                    .iter()
+                    .flat_map(|mood| spellings(Mood::TOKENS, *mood));
+                format!("{} AND {}", unnamed, lacks(token_regex(verbal)))
+            };
+            conditions.push(format!("({} OR ({}))", has(token_regex(own)), unknown));
+
+            if let Some(column) = part_of_speech {
+                let spelling = format!("lower(btrim({}, ' .'))", column);
+                let all = PartOfSpeech::TOKENS.iter().map(|(t, _)| *t);
+                conditions.push(format!(
+                    "({column} IS NULL OR {spelling} NOT IN ({all}) OR {spelling} IN ({own}))",
+                    column = column,
+                    spelling = spelling,
+                    all = sql_list(all),
+                    own = sql_list(spellings(PartOfSpeech::TOKENS, wanted)),
+                ));
+            }
+        }
+
+        let features = [
+            self.case.map(|v| token_regex(spellings(Case::TOKENS, v))),
+            self.number
+                .map(|v| token_regex(spellings(Number::TOKENS, v))),
+            self.gender
+                .map(|v| token_regex(spellings(Gender::TOKENS, v))),
+            self.tense.map(|v| token_regex(spellings(Tense::TOKENS, v))),
+            self.mood.map(|v| token_regex(spellings(Mood::TOKENS, v))),
+            self.voice.map(|v| token_regex(spellings(Voice::TOKENS, v))),
+            self.person
+                .map(|v| token_regex(spellings(Person::TOKENS, v))),
+            self.degree
+                .map(|v| token_regex(spellings(Degree::TOKENS, v))),
+        ];
+        conditions.extend(features.into_iter().flatten().map(has));
+
+        if conditions.is_empty() {
+            "TRUE".to_string()
+        } else {
+            conditions.join(" AND ")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+
+    #[test]
+    fn test_token_regex_matches_whole_tokens() {
+        let regex = Regex::new(&token_regex(spellings(Case::TOKENS, Case::Ablative))).unwrap();
+        assert!(regex.is_match("noun masc abl pl"));
+        assert!(regex.is_match("acc/abl"));
+        assert!(!regex.is_match("noun masc ablx pl"));
+        assert!(!regex.is_match("noun masc dat pl"));
+    }
+
+    #[test]
+    fn test_filter_sql() {
+        assert_eq!(QueryOptions::default().filter_sql("analysis", None), "TRUE");
+        let options = QueryOptions {
+            part_of_speech: Some(PartOfSpeech::Verb),
+            case: Some(Case::Ablative),
+            exact: true,
+            ..Default::default()
+        };
+        let sql = options.filter_sql("h.analysis", Some("v.part_of_speech"));
+        assert!(sql.contains("lower(h.analysis) ~ '(^|[^a-z0-9_])(abl|ablative)([^a-z0-9_]|$)'"));
+        assert!(sql.contains("v.part_of_speech IS NULL"));
+        assert!(sql.contains("IN ('verb', 'v')"));
+        assert!(!options
+            .filter_sql("h.analysis", None)
+            .contains("part_of_speech"));
+    }
+}
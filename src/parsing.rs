@@ -1,6 +1,6 @@
 use nom::bytes::complete::{tag, take_until};
 use nom::{combinator::opt, multi::many0, sequence::delimited, IResult};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, PartialEq, Clone)]
 struct Sense<'a> {
@@ -17,16 +17,68 @@ pub struct Entry<'a> {
     senses: Vec<Sense<'a>>,
 }
 
-impl<'a> Entry<'a> {
+/// An owned copy of a `Sense`, so parsed entries can outlive the XML line.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct LnsSense {
+    pub pos: Option<String>,
+    pub authors: Vec<String>,
+    pub i_tags: Vec<String>,
+}
+
+/// An owned copy of an `Entry`, as returned by the LNS lookups.
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct LnsEntry {
+    pub head: String,
+    pub orth_orig: String,
+    pub early_i_tags: Vec<String>,
+    pub senses: Vec<LnsSense>,
+    /// The `id` of the entry's `<div2>`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub xml_id: Option<String>,
+    /// Which of several entries spelled alike this is, e.g. 2 for `liber2`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub homograph: Option<u32>,
+}
+
+/// The homograph number at the end of an LNS key, if it has one.
+pub fn homograph_number(key: &str) -> Option<u32> {
+    let stem = key.trim_end_matches(|c: char| c.is_ascii_digit());
+    key[stem.len()..].parse().ok()
+}
+
+impl<'a> From<Sense<'a>> for LnsSense {
+    fn from(sense: Sense<'a>) -> Self {
+        LnsSense {
+            pos: sense.pos.map(String::from),
+            authors: sense.authors.into_iter().map(String::from).collect(),
+            i_tags: sense.i_tags.into_iter().map(String::from).collect(),
+        }
+    }
+}
+
+impl<'a> From<Entry<'a>> for LnsEntry {
+    fn from(entry: Entry<'a>) -> Self {
+        LnsEntry {
+            head: entry.head.to_string(),
+            orth_orig: entry.orth_orig.to_string(),
+            early_i_tags: entry.early_i_tags.into_iter().map(String::from).collect(),
+            senses: entry.senses.into_iter().map(LnsSense::from).collect(),
+            xml_id: None,
+            homograph: None,
+        }
+    }
+}
+
+impl Entry<'_> {
     pub fn print(&self) {
         println!("\n{}", self.head);
         println!("Pronunciation: {}", self.orth_orig);
-        if self.early_i_tags.len() > 0 {
+        if !self.early_i_tags.is_empty() {
             println!("{}", self.early_i_tags.join(", "));
         }
         let mut count = 0;
         for sense in self.senses.iter() {
-            if sense.i_tags.len() > 0 {
+            if !sense.i_tags.is_empty() {
                 count += 1;
                 println!("# Sense {}", count);
                 if let Some(pos) = sense.pos {
@@ -34,7 +86,7 @@ impl<'a> Entry<'a> {
                 }
                 println!("{}", sense.i_tags.join(", "));
             }
-            if sense.authors.len() > 0 {
+            if !sense.authors.is_empty() {
                 println!("Author(s): {}", sense.authors.join(", "))
             }
         }
@@ -45,7 +97,7 @@ impl<'a> Entry<'a> {
     }
 }
 
-pub fn parse_entry<'b>(input: &'b str) -> IResult<&'b str, Entry> {
+pub fn parse_entry(input: &str) -> IResult<&str, Entry<'_>> {
     let (tail, _) = take_until("orth_orig")(input)?;
     let (tail, orth_orig) = delimited(tag("orth_orig=\""), take_until("\""), tag("\""))(tail)?;
     let (tail, head) = delimited(tag(">"), take_until("</head>"), tag("</head>"))(tail)?;
@@ -63,7 +115,7 @@ pub fn parse_entry<'b>(input: &'b str) -> IResult<&'b str, Entry> {
     ))
 }
 
-fn chomp_and_return_sense<'a>(input: &'a str) -> IResult<&'a str, Sense> {
+fn chomp_and_return_sense(input: &str) -> IResult<&str, Sense<'_>> {
     let (tail, _) = take_until("<sense")(input)?;
     let (tail, _) = take_until(">")(tail)?;
     let (tail, sense_block) = delimited(tag(">"), take_until("</sense>"), tag("</sense>"))(tail)?;
@@ -171,4 +223,11 @@ mod tests {
             r#"{"head":"dominus","orth_orig":"dŏmĭnus","early_i_tags":["test","test2"],"senses":[{"pos":"test_pos","authors":["test_author1","test_author2"],"i_tags":["test","test2"]},{"pos":"test_pos","authors":["test_author1","test_author2"],"i_tags":["test","test2"]}]}"#
         );
     }
+
+    #[test]
+    fn test_homograph_number() {
+        assert_eq!(homograph_number("liber2"), Some(2));
+        assert_eq!(homograph_number("os1"), Some(1));
+        assert_eq!(homograph_number("do^mi^nus"), None);
+    }
 }
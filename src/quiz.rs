@@ -0,0 +1,300 @@
+use crate::analysis::PartOfSpeech;
+use crate::error::LatinDbError;
+use crate::models::VocabHit;
+use crate::sources::{list_rows, VocabSource};
+use diesel::pg::PgConnection;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+/// A small seeded generator (SplitMix64), so the same seed always gives
+/// the same paper.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`; `n` must not be zero.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// Which way round a question is asked.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    /// Show the Latin dictionary form; choose its meaning.
+    LatinToEnglish,
+    /// Show the meaning; choose the Latin dictionary form.
+    EnglishToLatin,
+}
+
+impl FromStr for Direction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "latin_to_english" | "l2e" | "latin" => Ok(Direction::LatinToEnglish),
+            "english_to_latin" | "e2l" | "english" => Ok(Direction::EnglishToLatin),
+            _ => Err(format!("unknown direction: {:?}", s)),
+        }
+    }
+}
+
+/// One multiple-choice question.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Question {
+    pub direction: Direction,
+    pub headword: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub part_of_speech: Option<String>,
+    pub prompt: String,
+    pub choices: Vec<String>,
+    /// The index of the right answer in `choices`.
+    pub answer: usize,
+}
+
+/// What `make_quiz` should produce.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuizOptions {
+    pub questions: usize,
+    /// Choices per question, the right answer included.
+    pub choices: usize,
+    /// Ask every question this way round, or mix both if `None`.
+    pub direction: Option<Direction>,
+    pub seed: u64,
+}
+
+impl Default for QuizOptions {
+    fn default() -> Self {
+        QuizOptions {
+            questions: 10,
+            choices: 4,
+            direction: None,
+            seed: 0,
+        }
+    }
+}
+
+/// How many of the nearest-length candidates distractors are drawn from,
+/// per distractor needed.
+const DISTRACTOR_SPREAD: usize = 3;
+
+fn same_part_of_speech(a: &VocabHit, b: &VocabHit) -> bool {
+    let parse = |hit: &VocabHit| {
+        hit.part_of_speech
+            .as_deref()
+            .and_then(PartOfSpeech::from_token)
+    };
+    match (parse(a), parse(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a.part_of_speech == b.part_of_speech,
+    }
+}
+
+fn choice_text(hit: &VocabHit, direction: Direction) -> &str {
+    match direction {
+        Direction::LatinToEnglish => &hit.meaning,
+        Direction::EnglishToLatin => &hit.dict_form,
+    }
+}
+
+/// Pick `count` wrong answers for `target`: rows with the same part of
+/// speech whose meaning is close in length, chosen at random from the
+/// nearest few so papers vary. Rows of other parts of speech are used
+/// only if there are too few of the same one.
+fn distractors<'a>(
+    target: &VocabHit,
+    rows: &'a [VocabHit],
+    direction: Direction,
+    count: usize,
+    rng: &mut Rng,
+) -> Vec<&'a VocabHit> {
+    let answer = choice_text(target, direction);
+    let mut seen: HashSet<&str> = HashSet::new();
+    let mut candidates: Vec<&VocabHit> = Vec::new();
+    for row in rows {
+        let text = choice_text(row, direction);
+        if row.headword != target.headword && text != answer && seen.insert(text) {
+            candidates.push(row);
+        }
+    }
+    let length = target.meaning.chars().count() as i64;
+    candidates.sort_by_key(|row| {
+        (
+            !same_part_of_speech(row, target),
+            (row.meaning.chars().count() as i64 - length).abs(),
+        )
+    });
+    let same = candidates
+        .iter()
+        .filter(|row| same_part_of_speech(row, target))
+        .count();
+    let pool = if same >= count {
+        same.min(count * DISTRACTOR_SPREAD)
+    } else {
+        count.min(candidates.len())
+    };
+    let mut nearest: Vec<&VocabHit> = candidates[..pool].to_vec();
+    rng.shuffle(&mut nearest);
+    nearest.truncate(count);
+    nearest
+}
+
+/// Build a multiple-choice paper from vocabulary rows.
+///
+/// Each row is asked about at most once. A question may end up with fewer
+/// than `options.choices` choices if the rows do not offer enough distinct
+/// wrong answers.
+pub fn make_quiz(rows: &[VocabHit], options: &QuizOptions) -> Vec<Question> {
+    let mut rng = Rng(options.seed);
+    let mut order: Vec<usize> = (0..rows.len()).collect();
+    rng.shuffle(&mut order);
+    order.truncate(options.questions);
+
+    order
+        .into_iter()
+        .map(|index| {
+            let target = &rows[index];
+            let direction = options.direction.unwrap_or(if rng.below(2) == 0 {
+                Direction::LatinToEnglish
+            } else {
+                Direction::EnglishToLatin
+            });
+            let wrong = distractors(
+                target,
+                rows,
+                direction,
+                options.choices.saturating_sub(1),
+                &mut rng,
+            );
+            let mut choices: Vec<&VocabHit> = wrong;
+            choices.push(target);
+            rng.shuffle(&mut choices);
+            let answer = choices
+                .iter()
+                .position(|hit| std::ptr::eq(*hit, target))
+                .unwrap_or_default();
+            let prompt = match direction {
+                Direction::LatinToEnglish => target.dict_form.clone(),
+                Direction::EnglishToLatin => target.meaning.clone(),
+            };
+            Question {
+                direction,
+                headword: target.headword.clone(),
+                part_of_speech: target.part_of_speech.clone(),
+                prompt,
+                choices: choices
+                    .iter()
+                    .map(|hit| choice_text(hit, direction).to_string())
+                    .collect(),
+                answer,
+            }
+        })
+        .collect()
+}
+
+/// Build a paper from the rows of `sources`, e.g. the GCSE and AS lists.
+/// A headword on several lists is taken from the first of them only.
+///
+/// Every row of every source is loaded and each question looks through all
+/// of them for distractors, so a paper drawn from `wwords`, with its tens of
+/// thousands of rows, is much slower to set than one from a curated list.
+pub fn quiz(
+    sources: &[&dyn VocabSource],
+    options: &QuizOptions,
+    connection: &PgConnection,
+) -> Result<Vec<Question>, LatinDbError> {
+    let mut headwords: HashSet<String> = HashSet::new();
+    let mut rows: Vec<VocabHit> = Vec::new();
+    for source in sources {
+        for row in list_rows(*source, connection)? {
+            if headwords.insert(row.headword.clone()) {
+                rows.push(row);
+            }
+        }
+    }
+    Ok(make_quiz(&rows, options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(id: i64, headword: &str, part_of_speech: &str, meaning: &str) -> VocabHit {
+        VocabHit {
+            id,
+            headword: headword.to_string(),
+            dict_form: format!("{}, dict", headword),
+            part_of_speech: Some(part_of_speech.to_string()),
+            class: None,
+            meaning: meaning.to_string(),
+            analyses: Vec::new(),
+        }
+    }
+
+    fn rows() -> Vec<VocabHit> {
+        vec![
+            hit(1, "servus", "noun", "slave"),
+            hit(2, "dominus", "noun", "master"),
+            hit(3, "amo", "verb", "love, like"),
+            hit(4, "porto", "verb", "carry"),
+            hit(5, "manus", "noun", "hand"),
+            hit(6, "equus", "noun", "horse"),
+            hit(7, "video", "verb", "see"),
+            hit(8, "et", "conj", "and"),
+        ]
+    }
+
+    #[test]
+    fn test_same_seed_same_paper() {
+        let options = QuizOptions {
+            seed: 42,
+            ..Default::default()
+        };
+        assert_eq!(make_quiz(&rows(), &options), make_quiz(&rows(), &options));
+        let other = QuizOptions {
+            seed: 43,
+            ..Default::default()
+        };
+        assert_ne!(make_quiz(&rows(), &options), make_quiz(&rows(), &other));
+    }
+
+    #[test]
+    fn test_distractors_share_part_of_speech() {
+        let rows = rows();
+        let options = QuizOptions {
+            questions: rows.len(),
+            choices: 3,
+            direction: Some(Direction::LatinToEnglish),
+            seed: 7,
+        };
+        for question in make_quiz(&rows, &options) {
+            let target = rows
+                .iter()
+                .find(|r| r.headword == question.headword)
+                .unwrap();
+            assert_eq!(question.choices[question.answer], target.meaning);
+            assert_eq!(question.choices.len(), 3);
+            if target.part_of_speech.as_deref() == Some("noun") {
+                for choice in &question.choices {
+                    let row = rows.iter().find(|r| &r.meaning == choice).unwrap();
+                    assert_eq!(row.part_of_speech.as_deref(), Some("noun"));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use crate::error::LatinDbError;
+use crate::membership::list_memberships;
+use crate::sources::VOCAB_SOURCES;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::sql_types::{Nullable, Text};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// How well a meaning matched the English searched for, best first.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchRank {
+    /// One of the comma- or semicolon-separated glosses is the search term,
+    /// ignoring a leading "to", "a", "an" or "the".
+    Exact,
+    /// The search term appears as a whole word or phrase.
+    Word,
+    /// The search term appears inside a longer word.
+    Substring,
+}
+
+/// A Latin headword whose meaning matched an English search.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ReverseHit {
+    pub headword: String,
+    pub dict_form: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub part_of_speech: Option<String>,
+    pub meaning: String,
+    pub rank: MatchRank,
+    /// The names of every source whose list contains this headword, as
+    /// reported by `list_memberships`, in registry order.
+    pub sources: Vec<String>,
+}
+
+#[derive(QueryableByName, Debug)]
+struct MeaningRow {
+    #[sql_type = "Text"]
+    headword: String,
+    #[sql_type = "Text"]
+    dict_form: String,
+    #[sql_type = "Nullable<Text>"]
+    part_of_speech: Option<String>,
+    #[sql_type = "Text"]
+    meaning: String,
+}
+
+fn strip_article(text: &str) -> &str {
+    for article in ["to ", "a ", "an ", "the "] {
+        if let Some(rest) = text.strip_prefix(article) {
+            return rest.trim_start();
+        }
+    }
+    text
+}
+
+fn normalize_gloss(gloss: &str) -> String {
+    // Drop parenthetical notes like "hand; band (of men)".
+    let mut plain = String::new();
+    let mut depth = 0;
+    for c in gloss.chars() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = (depth - 1).max(0),
+            _ if depth == 0 => plain.push(c),
+            _ => {}
+        }
+    }
+    let lower = plain.trim().to_lowercase();
+    strip_article(&lower).trim().to_string()
+}
+
+/// The pattern `rank_meaning` looks for `query` as a whole word with.
+/// `query` should already be normalized as a gloss.
+pub fn word_regex(query: &str) -> Regex {
+    Regex::new(&format!(r"(?i)\b{}\b", regex::escape(query)))
+        .expect("an escaped term is a valid pattern")
+}
+
+/// Rank `meaning` against the English `query`, normalized as a gloss, or
+/// `None` if it does not contain it at all. `word` is `word_regex(query)`,
+/// built once per search.
+pub fn rank_meaning(query: &str, word: &Regex, meaning: &str) -> Option<MatchRank> {
+    if query.is_empty() {
+        return None;
+    }
+    if meaning
+        .split([',', ';', '/'])
+        .any(|gloss| normalize_gloss(gloss) == query)
+    {
+        return Some(MatchRank::Exact);
+    }
+    if word.is_match(meaning) {
+        return Some(MatchRank::Word);
+    }
+    if meaning.to_lowercase().contains(query) {
+        return Some(MatchRank::Substring);
+    }
+    None
+}
+
+fn like_pattern(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+fn meaning_search_sql() -> String {
+    let branches: Vec<String> = VOCAB_SOURCES
+        .iter()
+        .map(|source| {
+            format!(
+                "SELECT v.headword, v.dict_form, v.part_of_speech, v.meaning \
+                 FROM ({}) v WHERE v.meaning ILIKE $1",
+                source.sql_select()
+            )
+        })
+        .collect();
+    branches.join(" UNION ALL ")
+}
+
+/// Find the Latin headwords whose meaning in any source mentions `english`.
+///
+/// Results are grouped by headword and ranked so exact glosses come before
+/// whole-word matches, which come before substring matches; within a rank,
+/// headwords on more lists come first. `limit` caps the number returned.
+pub fn reverse_lookup(
+    english: &str,
+    limit: Option<usize>,
+    connection: &PgConnection,
+) -> Result<Vec<ReverseHit>, LatinDbError> {
+    let needle = normalize_gloss(english);
+    if needle.is_empty() {
+        return Ok(Vec::new());
+    }
+    let rows: Vec<MeaningRow> = diesel::sql_query(meaning_search_sql())
+        .bind::<Text, _>(like_pattern(&needle))
+        .load(connection)?;
+
+    let word = word_regex(&needle);
+    let mut hits: Vec<ReverseHit> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for row in rows {
+        let rank = match rank_meaning(&needle, &word, &row.meaning) {
+            Some(rank) => rank,
+            None => continue,
+        };
+        match positions.get(&row.headword) {
+            Some(&index) => {
+                let hit = &mut hits[index];
+                if rank < hit.rank {
+                    hit.rank = rank;
+                    hit.dict_form = row.dict_form;
+                    hit.part_of_speech = row.part_of_speech;
+                    hit.meaning = row.meaning;
+                }
+            }
+            None => {
+                positions.insert(row.headword.clone(), hits.len());
+                hits.push(ReverseHit {
+                    headword: row.headword,
+                    dict_form: row.dict_form,
+                    part_of_speech: row.part_of_speech,
+                    meaning: row.meaning,
+                    rank,
+                    sources: Vec::new(),
+                });
+            }
+        }
+    }
+
+    let headwords: Vec<String> = hits.iter().map(|hit| hit.headword.clone()).collect();
+    let memberships: HashMap<String, _> = list_memberships(&headwords, connection)?
+        .into_iter()
+        .map(|membership| (membership.headword.clone(), membership))
+        .collect();
+    for hit in hits.iter_mut() {
+        if let Some(membership) = memberships.get(&hit.headword) {
+            hit.sources = VOCAB_SOURCES
+                .iter()
+                .map(|source| source.name())
+                .filter(|name| membership.contains(name))
+                .map(str::to_string)
+                .collect();
+        }
+    }
+    hits.sort_by(|a, b| {
+        a.rank
+            .cmp(&b.rank)
+            .then(b.sources.len().cmp(&a.sources.len()))
+            .then(a.headword.cmp(&b.headword))
+    });
+    if let Some(limit) = limit {
+        hits.truncate(limit);
+    }
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_meaning() {
+        let rank = |query: &str, meaning: &str| {
+            let query = normalize_gloss(query);
+            rank_meaning(&query, &word_regex(&query), meaning)
+        };
+        assert_eq!(rank("to carry", "carry, bring"), Some(MatchRank::Exact));
+        assert_eq!(rank("carry", "to carry"), Some(MatchRank::Exact));
+        assert_eq!(rank("hand", "hand; band (of men)"), Some(MatchRank::Exact));
+        assert_eq!(rank("carry", "carry out, perform"), Some(MatchRank::Word));
+        assert_eq!(rank("carry", "carrying"), Some(MatchRank::Substring));
+        assert_eq!(rank("carry", "bring"), None);
+    }
+
+    #[test]
+    fn test_like_pattern_escapes() {
+        assert_eq!(like_pattern("50%_off"), "%50\\%\\_off%");
+    }
+}
@@ -22,6 +22,21 @@ table! {
     }
 }
 
+table! {
+    card_states (id) {
+        id -> Int8,
+        learner_id -> Int8,
+        list -> Varchar,
+        entry_id -> Int8,
+        repetitions -> Int4,
+        interval_days -> Int4,
+        ease_factor -> Float8,
+        due_at -> Timestamp,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
 table! {
     clc4 (id) {
         id -> Int8,
@@ -45,6 +60,15 @@ table! {
     }
 }
 
+table! {
+    learners (id) {
+        id -> Int8,
+        name -> Varchar,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
 table! {
     lewis_short_lemmata (id) {
         id -> Int8,
@@ -58,6 +82,21 @@ table! {
     }
 }
 
+table! {
+    lns_entries (id) {
+        id -> Int8,
+        xml_id -> Varchar,
+        key -> Varchar,
+        head -> Varchar,
+        orth_orig -> Varchar,
+        early_i_tags -> Jsonb,
+        senses -> Jsonb,
+        raw_xml -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
 table! {
     lns_entry_keys (id) {
         id -> Int8,
@@ -70,6 +109,19 @@ table! {
     }
 }
 
+table! {
+    review_records (id) {
+        id -> Int8,
+        learner_id -> Int8,
+        list -> Varchar,
+        entry_id -> Int8,
+        grade -> Int2,
+        reviewed_at -> Timestamp,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
 table! {
     wwords (id) {
         id -> Int8,
@@ -83,12 +135,19 @@ table! {
     }
 }
 
+joinable!(card_states -> learners (learner_id));
+joinable!(review_records -> learners (learner_id));
+
 allow_tables_to_appear_in_same_query!(
     asvocab,
     asvocab2,
+    card_states,
     clc4,
     gcse_latin,
+    learners,
     lewis_short_lemmata,
+    lns_entries,
     lns_entry_keys,
+    review_records,
     wwords,
 );
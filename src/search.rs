@@ -1,11 +1,122 @@
-use crate::parsing::parse_entry;
+use crate::config::LnsConfig;
+use crate::error::LatinDbError;
+use crate::lns_cache::{CacheStats, LnsCache};
+use crate::lns_index::{entry_attribute, LnsIndex, INDEX_FILE};
+use crate::lns_store::entries_imported;
+use crate::parsing::{homograph_number, parse_entry, LnsEntry};
+use diesel::pg::PgConnection;
 use lazy_static::lazy_static;
-use regex::Regex;
 use std::collections::HashMap;
-use std::error::Error;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::io::{BufRead, BufReader, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard};
+
+/// The LNS directory in use, if one has been given, the index found in it,
+/// if any, and the entries parsed from it so far.
+struct LnsData {
+    dir: Option<PathBuf>,
+    index: Option<LnsIndex>,
+    /// Why the index in the directory is not being used, if it is not.
+    index_error: Option<String>,
+    cache: LnsCache,
+}
+
+impl LnsData {
+    fn load(config: &LnsConfig) -> Result<Self, LatinDbError> {
+        Ok(LnsData {
+            dir: Some(config.data_dir.clone()),
+            index: LnsIndex::load_if_present(&config.data_dir.join(INDEX_FILE))?,
+            index_error: None,
+            cache: LnsCache::new(config.cache_capacity),
+        })
+    }
+
+    /// As `load` for `LnsConfig::from_env()`, but scanning the XML files if
+    /// the index is unusable, and recording why in `index_error`. Without
+    /// `LATIN_LNS_DIR` there is no directory, and every read fails with
+    /// `LnsDirUnset`.
+    fn from_env() -> Self {
+        let config = match LnsConfig::from_env() {
+            Ok(config) => config,
+            Err(_) => {
+                return LnsData {
+                    dir: None,
+                    index: None,
+                    index_error: None,
+                    cache: LnsCache::new(0),
+                }
+            }
+        };
+        LnsData::load(&config).unwrap_or_else(|e| LnsData {
+            dir: Some(config.data_dir.clone()),
+            index: None,
+            index_error: Some(e.to_string()),
+            cache: LnsCache::new(config.cache_capacity),
+        })
+    }
+
+    fn dir(&self) -> Result<&Path, LatinDbError> {
+        self.dir.as_deref().ok_or(LatinDbError::LnsDirUnset)
+    }
+}
+
+lazy_static! {
+    static ref LNS_DATA: RwLock<LnsData> = RwLock::new(LnsData::from_env());
+}
+
+fn lns_data() -> RwLockReadGuard<'static, LnsData> {
+    LNS_DATA.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Read the LNS entries from `config.data_dir` from now on, after checking
+/// that it has every XML file and that its index, if it has one, parses.
+/// Binaries should call this at startup so a wrong directory is reported
+/// before any lookup.
+pub fn configure_lns(config: &LnsConfig) -> Result<(), LatinDbError> {
+    config.validate()?;
+    let data = LnsData::load(config)?;
+    *LNS_DATA.write().unwrap_or_else(PoisonError::into_inner) = data;
+    Ok(())
+}
+
+/// As `configure_lns`, for lookups that read imported entries from
+/// `lns_entries` before the XML files. Once entries have been imported the
+/// directory is only a fallback for those that were not, so a directory
+/// that is unset or incomplete is returned as a warning rather than an
+/// error, and any directory given is still used as far as it goes.
+pub fn configure_lns_for_lookups(
+    config: Result<LnsConfig, LatinDbError>,
+    connection: &PgConnection,
+) -> Result<Option<LatinDbError>, LatinDbError> {
+    let (config, error) = match config {
+        Ok(config) => match configure_lns(&config) {
+            Ok(()) => return Ok(None),
+            Err(e) => (Some(config), e),
+        },
+        Err(e) => (None, e),
+    };
+    if !entries_imported(connection)? {
+        return Err(error);
+    }
+    if let Some(config) = config {
+        let data = LnsData::load(&config)?;
+        *LNS_DATA.write().unwrap_or_else(PoisonError::into_inner) = data;
+    }
+    Ok(Some(error))
+}
+
+/// Why the index in `LATIN_LNS_DIR` is being ignored, if it is. Only the
+/// directory taken from the environment falls back to scanning the XML
+/// files; `configure_lns` returns the error instead.
+pub fn lns_index_error() -> Option<String> {
+    lns_data().index_error.clone()
+}
+
+/// Hits, misses and size of the cache of parsed entries.
+pub fn lns_cache_stats() -> CacheStats {
+    lns_data().cache.stats()
+}
 
 lazy_static! {
     pub static ref XML_FILES: HashMap<char, &'static str> = {
@@ -40,51 +151,90 @@ lazy_static! {
     };
 }
 
-pub fn search(term: &str, file: &str) -> Result<String, Box<dyn Error>> {
-    let path = Path::new("/home/simon/Projects/python/latindictionary/data");
-
-    let mut found = String::new();
-    let full_path = path.join(file);
+/// Find the line of `file` holding the entry whose key is `term`.
+///
+/// Returns `Ok(None)` if the file has no such entry.
+pub fn search(term: &str, file: &str) -> Result<Option<String>, LatinDbError> {
+    let full_path = lns_data().dir()?.join(file);
     // println!("path: {:?}", full_path);
-    let file_text = File::open(full_path)?;
+    let file_text = match File::open(&full_path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            return Err(LatinDbError::LnsFileMissing(full_path))
+        }
+        Err(e) => return Err(LatinDbError::Io(e)),
+    };
     let buffered_text = BufReader::new(file_text);
-    let ptn = format!(r#"key="{}""#, regex::escape(term));
-    let rgx = Regex::new(&ptn)?;
+    let ptn = format!(r#"key="{}""#, term);
     for line in buffered_text.lines() {
         let line = line?;
-        if rgx.is_match(&line) {
-            found = line;
-            break;
+        if line.contains(&ptn) {
+            return Ok(Some(line));
         }
     }
-    Ok(found)
-}
-
-pub fn query_lns_vec(term_vec: Vec<String>) -> Result<String, Box<dyn std::error::Error>> {
-    let mut lines_found = Vec::new();
-    let mut entries_found = Vec::new();
-    for term in term_vec {
-        let initial: char = term.chars().next().unwrap().to_lowercase().next().unwrap();
-        let file = XML_FILES.get(&initial).unwrap();
-        let query_result = search(&term, &file);
-        match query_result {
-            Ok(line) => lines_found.push(line),
-            Err(e) => eprintln!("{e}"),
+    Ok(None)
+}
+
+/// Find the line for `term`, seeking straight to it if the index has it
+/// and scanning `file` otherwise.
+fn find_entry(term: &str, file: &str) -> Result<Option<String>, LatinDbError> {
+    {
+        let data = lns_data();
+        if let Some(index) = data.index.as_ref() {
+            if let Some(line) = index.read_entry(data.dir()?, term)? {
+                return Ok(Some(line));
+            }
         }
     }
+    search(term, file)
+}
 
-    for line in lines_found.iter() {
-        let parsed_entry = parse_entry(line);
-        match parsed_entry {
-            Ok((_, entry)) => entries_found.push(entry),
-            Err(e) => eprintln!("{e}"),
-        }
+/// Read and parse the entry for `term` from the XML files.
+fn read_lns_entry(term: &str) -> Result<Option<LnsEntry>, LatinDbError> {
+    let initial = term.chars().next().and_then(|c| c.to_lowercase().next());
+    let file = match initial.and_then(|c| XML_FILES.get(&c)) {
+        Some(file) => file,
+        None => return Err(LatinDbError::UnknownInitial(term.to_string())),
+    };
+    let line = match find_entry(term, file)? {
+        Some(line) => line,
+        None => return Ok(None),
+    };
+    match parse_entry(&line) {
+        Ok((_, entry)) => Ok(Some(LnsEntry {
+            xml_id: entry_attribute(&line, "id").map(String::from),
+            homograph: homograph_number(term),
+            ..LnsEntry::from(entry)
+        })),
+        Err(e) => Err(LatinDbError::XmlParse {
+            key: term.to_string(),
+            message: e.to_string(),
+        }),
     }
+}
+
+/// The cached entry for `key`, counting the lookup as a hit or a miss.
+pub(crate) fn cached_lns_entry(key: &str) -> Option<LnsEntry> {
+    lns_data().cache.get(key)
+}
+
+/// Cache `entry` as the entry for `key`, as read from somewhere else.
+pub(crate) fn cache_lns_entry(key: &str, entry: LnsEntry) {
+    lns_data().cache.insert(key, entry);
+}
 
-    match serde_json::to_string(&entries_found) {
-        Ok(json) => Ok(json),
-        Err(e) => Err(Box::new(e)),
+/// Read the entry for `key` from the XML files and cache it.
+pub(crate) fn read_and_cache_lns_entry(key: &str) -> Result<Option<LnsEntry>, LatinDbError> {
+    let entry = read_lns_entry(key)?;
+    if let Some(entry) = &entry {
+        cache_lns_entry(key, entry.clone());
     }
+    Ok(entry)
+}
+
+/// The cached entry for `key`, without counting the lookup.
+pub(crate) fn touch_lns_entry(key: &str) -> Option<LnsEntry> {
+    lns_data().cache.touch(key)
 }
 
 #[cfg(test)]
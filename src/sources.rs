@@ -0,0 +1,348 @@
+use crate::error::LatinDbError;
+use crate::models::{group_hits, ClcRow, ListRow, VocabHit, WwordsRow};
+use crate::normalize::column_matches;
+use crate::options::QueryOptions;
+use crate::schema::{asvocab, clc4, gcse_latin, wwords};
+use diesel::dsl::sql;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Bool, Nullable, Text};
+
+/// A vocabulary list that can be searched by inflected form or by headword.
+///
+/// Each of the vocabulary tables in `schema.rs` implements this, and
+/// `VOCAB_SOURCES` lists them all, so adding a new list means adding one
+/// impl and one entry in the registry.
+pub trait VocabSource: Sync {
+    /// The key this source's hits are reported under, e.g. `"gcse"`.
+    fn name(&self) -> &'static str;
+
+    /// Find rows whose headword has `term` among its forms in
+    /// `lewis_short_lemmata`, keeping only the readings `options` allows.
+    /// Forms are compared normalized unless `options.exact` is set.
+    /// Each row is returned once, with every matching analysis, ordered by
+    /// headword and then dict_form.
+    fn lookup_form(
+        &self,
+        term: &str,
+        options: &QueryOptions,
+        connection: &PgConnection,
+    ) -> Result<Vec<VocabHit>, LatinDbError>;
+
+    /// Find rows whose headword is `term`, ordered by dict_form. Unless
+    /// `exact` is set, both are normalized before comparing.
+    fn lookup_headword(
+        &self,
+        term: &str,
+        exact: bool,
+        connection: &PgConnection,
+    ) -> Result<Vec<VocabHit>, LatinDbError>;
+
+    /// A `SELECT` over the whole table producing the columns `id, headword,
+    /// dict_form, part_of_speech, class, meaning`, with `NULL` for any the
+    /// table lacks. The aggregate lookups union these into one query.
+    fn sql_select(&self) -> &'static str;
+}
+
+pub static VOCAB_SOURCES: [&dyn VocabSource; 4] = [
+    &gcse_latin::table,
+    &clc4::table,
+    &asvocab::table,
+    &wwords::table,
+];
+
+/// Look up a registered source by its `name()`.
+pub fn vocab_source(name: &str) -> Option<&'static dyn VocabSource> {
+    VOCAB_SOURCES.iter().copied().find(|s| s.name() == name)
+}
+
+#[derive(QueryableByName, Debug)]
+struct SelectRow {
+    #[sql_type = "BigInt"]
+    id: i64,
+    #[sql_type = "Text"]
+    headword: String,
+    #[sql_type = "Text"]
+    dict_form: String,
+    #[sql_type = "Nullable<Text>"]
+    part_of_speech: Option<String>,
+    #[sql_type = "Nullable<Text>"]
+    class: Option<String>,
+    #[sql_type = "Text"]
+    meaning: String,
+}
+
+impl From<SelectRow> for VocabHit {
+    fn from(row: SelectRow) -> Self {
+        VocabHit {
+            id: row.id,
+            headword: row.headword,
+            dict_form: row.dict_form,
+            part_of_speech: row.part_of_speech,
+            class: row.class,
+            meaning: row.meaning,
+            analyses: Vec::new(),
+        }
+    }
+}
+
+/// Every row of `source`, in `id` order.
+pub fn list_rows(
+    source: &dyn VocabSource,
+    connection: &PgConnection,
+) -> Result<Vec<VocabHit>, LatinDbError> {
+    let rows: Vec<SelectRow> =
+        diesel::sql_query(format!("{} ORDER BY id", source.sql_select())).load(connection)?;
+    Ok(rows.into_iter().map(VocabHit::from).collect())
+}
+
+impl VocabSource for gcse_latin::table {
+    fn name(&self) -> &'static str {
+        "gcse"
+    }
+
+    fn lookup_form(
+        &self,
+        term: &str,
+        options: &QueryOptions,
+        connection: &PgConnection,
+    ) -> Result<Vec<VocabHit>, LatinDbError> {
+        use crate::schema::gcse_latin::dsl::dict_form as g_dict_form;
+        use crate::schema::gcse_latin::dsl::gcse_latin;
+        use crate::schema::gcse_latin::dsl::headword as g_headword;
+        use crate::schema::gcse_latin::dsl::id as g_id;
+        use crate::schema::gcse_latin::dsl::meaning as g_meaning;
+        use crate::schema::gcse_latin::dsl::part_of_speech as g_part_of_speech;
+        use crate::schema::lewis_short_lemmata::dsl::*;
+
+        let results = gcse_latin
+            .inner_join(lewis_short_lemmata.on(g_headword.eq(headword)))
+            .filter(column_matches(form, term, options.exact))
+            .filter(sql::<Bool>(&options.filter_sql(
+                "lewis_short_lemmata.analysis",
+                Some("gcse_latin.part_of_speech"),
+            )))
+            .select((
+                (g_id, g_headword, g_dict_form, g_part_of_speech, g_meaning),
+                analysis,
+            ))
+            .order((g_headword.asc(), g_dict_form.asc(), analysis.asc()))
+            .load::<(ListRow, String)>(connection)?;
+
+        let hits = results
+            .into_iter()
+            .map(|(row, a)| VocabHit::from(row).with_analysis(&a))
+            .collect();
+        Ok(group_hits(hits))
+    }
+
+    fn lookup_headword(
+        &self,
+        term: &str,
+        exact: bool,
+        connection: &PgConnection,
+    ) -> Result<Vec<VocabHit>, LatinDbError> {
+        use crate::schema::gcse_latin::dsl::*;
+
+        let results = gcse_latin
+            .filter(column_matches(headword, term, exact))
+            .select((id, headword, dict_form, part_of_speech, meaning))
+            .order(dict_form.asc())
+            .load::<ListRow>(connection)?;
+
+        Ok(results.into_iter().map(VocabHit::from).collect())
+    }
+
+    fn sql_select(&self) -> &'static str {
+        "SELECT id, headword, dict_form, part_of_speech, NULL::varchar AS class, meaning FROM gcse_latin"
+    }
+}
+
+impl VocabSource for clc4::table {
+    fn name(&self) -> &'static str {
+        "clc"
+    }
+
+    fn lookup_form(
+        &self,
+        term: &str,
+        options: &QueryOptions,
+        connection: &PgConnection,
+    ) -> Result<Vec<VocabHit>, LatinDbError> {
+        use crate::schema::clc4::dsl::clc4;
+        use crate::schema::clc4::dsl::dict_form as c_dict_form;
+        use crate::schema::clc4::dsl::headword as c_headword;
+        use crate::schema::clc4::dsl::id as c_id;
+        use crate::schema::clc4::dsl::meaning as c_meaning;
+        use crate::schema::lewis_short_lemmata::dsl::*;
+
+        let results = clc4
+            .inner_join(lewis_short_lemmata.on(c_headword.eq(headword)))
+            .filter(column_matches(form, term, options.exact))
+            .filter(sql::<Bool>(
+                &options.filter_sql("lewis_short_lemmata.analysis", None),
+            ))
+            .select(((c_id, c_headword, c_dict_form, c_meaning), analysis))
+            .order((c_headword.asc(), c_dict_form.asc(), analysis.asc()))
+            .load::<(ClcRow, String)>(connection)?;
+
+        let hits = results
+            .into_iter()
+            .map(|(row, a)| VocabHit::from(row).with_analysis(&a))
+            .collect();
+        Ok(group_hits(hits))
+    }
+
+    fn lookup_headword(
+        &self,
+        term: &str,
+        exact: bool,
+        connection: &PgConnection,
+    ) -> Result<Vec<VocabHit>, LatinDbError> {
+        use crate::schema::clc4::dsl::*;
+
+        let results = clc4
+            .filter(column_matches(headword, term, exact))
+            .select((id, headword, dict_form, meaning))
+            .order(dict_form.asc())
+            .load::<ClcRow>(connection)?;
+
+        Ok(results.into_iter().map(VocabHit::from).collect())
+    }
+
+    fn sql_select(&self) -> &'static str {
+        "SELECT id, headword, dict_form, NULL::varchar AS part_of_speech, NULL::varchar AS class, meaning FROM clc4"
+    }
+}
+
+impl VocabSource for asvocab::table {
+    fn name(&self) -> &'static str {
+        "asvocab"
+    }
+
+    fn lookup_form(
+        &self,
+        term: &str,
+        options: &QueryOptions,
+        connection: &PgConnection,
+    ) -> Result<Vec<VocabHit>, LatinDbError> {
+        use crate::schema::asvocab::dsl::asvocab;
+        use crate::schema::asvocab::dsl::dict_form as a_dict_form;
+        use crate::schema::asvocab::dsl::headword as a_headword;
+        use crate::schema::asvocab::dsl::id as a_id;
+        use crate::schema::asvocab::dsl::meaning as a_meaning;
+        use crate::schema::asvocab::dsl::part_of_speech as a_part_of_speech;
+        use crate::schema::lewis_short_lemmata::dsl::*;
+
+        let results = asvocab
+            .inner_join(lewis_short_lemmata.on(a_headword.eq(headword)))
+            .filter(column_matches(form, term, options.exact))
+            .filter(sql::<Bool>(&options.filter_sql(
+                "lewis_short_lemmata.analysis",
+                Some("asvocab.part_of_speech"),
+            )))
+            .select((
+                (a_id, a_headword, a_dict_form, a_part_of_speech, a_meaning),
+                analysis,
+            ))
+            .order((a_headword.asc(), a_dict_form.asc(), analysis.asc()))
+            .load::<(ListRow, String)>(connection)?;
+
+        let hits = results
+            .into_iter()
+            .map(|(row, a)| VocabHit::from(row).with_analysis(&a))
+            .collect();
+        Ok(group_hits(hits))
+    }
+
+    fn lookup_headword(
+        &self,
+        term: &str,
+        exact: bool,
+        connection: &PgConnection,
+    ) -> Result<Vec<VocabHit>, LatinDbError> {
+        use crate::schema::asvocab::dsl::*;
+
+        let results = asvocab
+            .filter(column_matches(headword, term, exact))
+            .select((id, headword, dict_form, part_of_speech, meaning))
+            .order(dict_form.asc())
+            .load::<ListRow>(connection)?;
+
+        Ok(results.into_iter().map(VocabHit::from).collect())
+    }
+
+    fn sql_select(&self) -> &'static str {
+        "SELECT id, headword, dict_form, part_of_speech, NULL::varchar AS class, meaning FROM asvocab"
+    }
+}
+
+impl VocabSource for wwords::table {
+    fn name(&self) -> &'static str {
+        "wwords"
+    }
+
+    fn lookup_form(
+        &self,
+        term: &str,
+        options: &QueryOptions,
+        connection: &PgConnection,
+    ) -> Result<Vec<VocabHit>, LatinDbError> {
+        use crate::schema::lewis_short_lemmata::dsl::*;
+        use crate::schema::wwords::dsl::class as w_class;
+        use crate::schema::wwords::dsl::dict_form as w_dict_form;
+        use crate::schema::wwords::dsl::headword as w_headword;
+        use crate::schema::wwords::dsl::id as w_id;
+        use crate::schema::wwords::dsl::meaning as w_meaning;
+        use crate::schema::wwords::dsl::part_of_speech as w_part_of_speech;
+        use crate::schema::wwords::dsl::wwords;
+
+        let results = wwords
+            .inner_join(lewis_short_lemmata.on(w_headword.eq(headword)))
+            .filter(column_matches(form, term, options.exact))
+            .filter(sql::<Bool>(&options.filter_sql(
+                "lewis_short_lemmata.analysis",
+                Some("wwords.part_of_speech"),
+            )))
+            .select((
+                (
+                    w_id,
+                    w_headword,
+                    w_dict_form,
+                    w_part_of_speech,
+                    w_class,
+                    w_meaning,
+                ),
+                analysis,
+            ))
+            .order((w_headword.asc(), w_dict_form.asc(), analysis.asc()))
+            .load::<(WwordsRow, String)>(connection)?;
+
+        let hits = results
+            .into_iter()
+            .map(|(row, a)| VocabHit::from(row).with_analysis(&a))
+            .collect();
+        Ok(group_hits(hits))
+    }
+
+    fn lookup_headword(
+        &self,
+        term: &str,
+        exact: bool,
+        connection: &PgConnection,
+    ) -> Result<Vec<VocabHit>, LatinDbError> {
+        use crate::schema::wwords::dsl::*;
+
+        let results = wwords
+            .filter(column_matches(headword, term, exact))
+            .select((id, headword, dict_form, part_of_speech, class, meaning))
+            .order(dict_form.asc())
+            .load::<WwordsRow>(connection)?;
+
+        Ok(results.into_iter().map(VocabHit::from).collect())
+    }
+
+    fn sql_select(&self) -> &'static str {
+        "SELECT id, headword, dict_form, part_of_speech, class, meaning FROM wwords"
+    }
+}
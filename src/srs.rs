@@ -0,0 +1,255 @@
+use crate::error::LatinDbError;
+use crate::models::{CardState, Learner, NewCardState, NewLearner, NewReviewRecord, VocabHit};
+use crate::schema::{card_states, learners, review_records};
+use crate::sources::{list_rows, vocab_source, VocabSource};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, SystemTime};
+
+/// The lists progress can be tracked against, by source name.
+pub const SRS_LISTS: [&str; 3] = ["gcse", "asvocab", "clc"];
+
+/// The best grade a review can be given; 3 and up count as remembered.
+pub const MAX_GRADE: i16 = 5;
+const PASS_GRADE: i16 = 3;
+const MIN_EASE_FACTOR: f64 = 1.3;
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// The SM-2 scheduling state of one card.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sm2 {
+    /// Reviews in a row graded 3 or better.
+    pub repetitions: i32,
+    pub interval_days: i32,
+    pub ease_factor: f64,
+}
+
+impl Default for Sm2 {
+    /// A card that has never been reviewed.
+    fn default() -> Self {
+        Sm2 {
+            repetitions: 0,
+            interval_days: 0,
+            ease_factor: 2.5,
+        }
+    }
+}
+
+impl From<&CardState> for Sm2 {
+    fn from(state: &CardState) -> Self {
+        Sm2 {
+            repetitions: state.repetitions,
+            interval_days: state.interval_days,
+            ease_factor: state.ease_factor,
+        }
+    }
+}
+
+impl Sm2 {
+    /// The state after a review graded `grade` (0 to 5), as in SuperMemo 2:
+    /// a pass schedules the card 1 day, then 6 days, then the last interval
+    /// times the ease factor ahead; a fail starts it again from a day.
+    pub fn review(self, grade: i16) -> Sm2 {
+        let lapse = f64::from(MAX_GRADE - grade);
+        let ease_factor =
+            (self.ease_factor + 0.1 - lapse * (0.08 + lapse * 0.02)).max(MIN_EASE_FACTOR);
+        if grade < PASS_GRADE {
+            return Sm2 {
+                repetitions: 0,
+                interval_days: 1,
+                ease_factor,
+            };
+        }
+        let interval_days = match self.repetitions {
+            0 => 1,
+            1 => 6,
+            _ => (f64::from(self.interval_days) * self.ease_factor).round() as i32,
+        };
+        Sm2 {
+            repetitions: self.repetitions + 1,
+            interval_days,
+            ease_factor,
+        }
+    }
+}
+
+fn tracked_list(list: &str) -> Result<&'static dyn VocabSource, LatinDbError> {
+    if !SRS_LISTS.contains(&list) {
+        return Err(LatinDbError::UnknownList(list.to_string()));
+    }
+    vocab_source(list).ok_or_else(|| LatinDbError::UnknownList(list.to_string()))
+}
+
+pub fn create_learner(name: &str, connection: &PgConnection) -> Result<Learner, LatinDbError> {
+    let learner = diesel::insert_into(learners::table)
+        .values(&NewLearner { name })
+        .get_result(connection)?;
+    Ok(learner)
+}
+
+/// Record that the learner answered `entry_id` on `list` with `grade`, and
+/// reschedule the card. The review and the new card state are saved
+/// together.
+pub fn record_review(
+    learner_id: i64,
+    list: &str,
+    entry_id: i64,
+    grade: i16,
+    now: SystemTime,
+    connection: &PgConnection,
+) -> Result<CardState, LatinDbError> {
+    tracked_list(list)?;
+    if !(0..=MAX_GRADE).contains(&grade) {
+        return Err(LatinDbError::InvalidGrade(grade));
+    }
+
+    connection.transaction::<_, LatinDbError, _>(|| {
+        diesel::insert_into(review_records::table)
+            .values(&NewReviewRecord {
+                learner_id,
+                list,
+                entry_id,
+                grade,
+                reviewed_at: now,
+            })
+            .execute(connection)?;
+
+        // Make sure the card exists, then lock it, so that two reviews of
+        // the same card at once are applied one after the other.
+        let sm2 = Sm2::default();
+        diesel::insert_into(card_states::table)
+            .values(&NewCardState {
+                learner_id,
+                list,
+                entry_id,
+                repetitions: sm2.repetitions,
+                interval_days: sm2.interval_days,
+                ease_factor: sm2.ease_factor,
+                due_at: now,
+                updated_at: now,
+            })
+            .on_conflict((
+                card_states::learner_id,
+                card_states::list,
+                card_states::entry_id,
+            ))
+            .do_nothing()
+            .execute(connection)?;
+        let existing: CardState = card_states::table
+            .filter(card_states::learner_id.eq(learner_id))
+            .filter(card_states::list.eq(list))
+            .filter(card_states::entry_id.eq(entry_id))
+            .for_update()
+            .first(connection)?;
+
+        let next = Sm2::from(&existing).review(grade);
+        let values = NewCardState {
+            learner_id,
+            list,
+            entry_id,
+            repetitions: next.repetitions,
+            interval_days: next.interval_days,
+            ease_factor: next.ease_factor,
+            due_at: now + Duration::from_secs(next.interval_days as u64 * SECONDS_PER_DAY),
+            updated_at: now,
+        };
+        let state = diesel::update(card_states::table.find(existing.id))
+            .set(&values)
+            .get_result(connection)?;
+        Ok(state)
+    })
+}
+
+/// A word to review, with its card state, or `None` if it is new.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DueCard {
+    pub entry: VocabHit,
+    pub state: Option<CardState>,
+}
+
+/// Up to `limit` words from `list` for the learner to review now: first
+/// the cards that have fallen due, most overdue first, then words never
+/// seen before, in list order.
+pub fn due_cards(
+    learner_id: i64,
+    list: &str,
+    limit: i64,
+    now: SystemTime,
+    connection: &PgConnection,
+) -> Result<Vec<DueCard>, LatinDbError> {
+    let source = tracked_list(list)?;
+
+    let due: Vec<CardState> = card_states::table
+        .filter(card_states::learner_id.eq(learner_id))
+        .filter(card_states::list.eq(list))
+        .filter(card_states::due_at.le(now))
+        .order((card_states::due_at.asc(), card_states::entry_id.asc()))
+        .limit(limit)
+        .load(connection)?;
+    let mut entries: HashMap<i64, VocabHit> = list_rows(source, connection)?
+        .into_iter()
+        .map(|hit| (hit.id, hit))
+        .collect();
+
+    let mut cards: Vec<DueCard> = due
+        .into_iter()
+        .filter_map(|state| {
+            entries.remove(&state.entry_id).map(|entry| DueCard {
+                entry,
+                state: Some(state),
+            })
+        })
+        .collect();
+
+    let remaining = limit - cards.len() as i64;
+    if remaining > 0 {
+        let seen: HashSet<i64> = card_states::table
+            .filter(card_states::learner_id.eq(learner_id))
+            .filter(card_states::list.eq(list))
+            .select(card_states::entry_id)
+            .load::<i64>(connection)?
+            .into_iter()
+            .collect();
+        let mut new: Vec<VocabHit> = entries
+            .into_values()
+            .filter(|entry| !seen.contains(&entry.id))
+            .collect();
+        new.sort_by_key(|entry| entry.id);
+        new.truncate(remaining as usize);
+        cards.extend(new.into_iter().map(|entry| DueCard { entry, state: None }));
+    }
+    Ok(cards)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sm2_intervals_grow_on_success() {
+        let first = Sm2::default().review(5);
+        assert_eq!((first.repetitions, first.interval_days), (1, 1));
+        let second = first.review(5);
+        assert_eq!((second.repetitions, second.interval_days), (2, 6));
+        let third = second.review(4);
+        assert_eq!(third.repetitions, 3);
+        assert_eq!(
+            third.interval_days,
+            (6.0 * second.ease_factor).round() as i32
+        );
+        assert!(second.ease_factor > Sm2::default().ease_factor);
+    }
+
+    #[test]
+    fn test_sm2_lapse_resets_and_eases_floor() {
+        let mut state = Sm2::default().review(5).review(5);
+        state = state.review(1);
+        assert_eq!((state.repetitions, state.interval_days), (0, 1));
+        for _ in 0..20 {
+            state = state.review(0);
+        }
+        assert_eq!(state.ease_factor, MIN_EASE_FACTOR);
+    }
+}
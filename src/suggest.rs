@@ -0,0 +1,103 @@
+use crate::error::LatinDbError;
+use crate::normalize::{fold_sql, normalize};
+use crate::sources::VOCAB_SOURCES;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::sql_types::{Array, BigInt, Bool, Text};
+use serde::Serialize;
+
+/// A form or headword starting with the prefix typed so far.
+#[derive(QueryableByName, Serialize, Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    #[sql_type = "Text"]
+    pub word: String,
+    /// The headwords `word` is a form of (or `word` itself, for a headword).
+    #[sql_type = "Array<Text>"]
+    pub headwords: Vec<String>,
+    /// Whether any of those headwords is on the GCSE list.
+    #[sql_type = "Bool"]
+    pub gcse: bool,
+    /// Whether any of those headwords is on the AS list.
+    #[sql_type = "Bool"]
+    pub asvocab: bool,
+}
+
+fn prefix_pattern(prefix: &str) -> String {
+    let escaped = prefix
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("{}%", escaped)
+}
+
+/// The candidates are matched with `latin_fold(column) LIKE 'prefix%'`,
+/// which the `text_pattern_ops` indexes on the folded form and headword
+/// columns answer as a range scan.
+fn suggest_sql() -> String {
+    let mut branches = vec![format!(
+        "SELECT form AS word, headword FROM lewis_short_lemmata WHERE {} LIKE $1",
+        fold_sql("form")
+    )];
+    for source in VOCAB_SOURCES {
+        branches.push(format!(
+            "SELECT v.headword AS word, v.headword FROM ({}) v WHERE {} LIKE $1",
+            source.sql_select(),
+            fold_sql("v.headword")
+        ));
+    }
+    format!(
+        "WITH candidates AS ({}) \
+         SELECT c.word, array_agg(DISTINCT c.headword ORDER BY c.headword) AS headwords, \
+         bool_or(EXISTS (SELECT 1 FROM gcse_latin g WHERE g.headword = c.headword)) AS gcse, \
+         bool_or(EXISTS (SELECT 1 FROM asvocab a WHERE a.headword = c.headword)) AS asvocab \
+         FROM candidates c GROUP BY c.word \
+         ORDER BY gcse DESC, asvocab DESC, length(c.word), c.word \
+         LIMIT $2 OFFSET $3",
+        branches.join(" UNION ")
+    )
+}
+
+/// Forms and headwords beginning with `prefix`, for as-you-type lookup.
+///
+/// Words whose headword is on the GCSE list come first, then those on the
+/// AS list, then the rest; shorter words come before longer ones. Fetch
+/// further pages by raising `offset` in steps of `limit`. The prefix is
+/// matched normalized, so "iuv" also finds words spelled with j or v.
+pub fn suggest(
+    prefix: &str,
+    limit: i64,
+    offset: i64,
+    connection: &PgConnection,
+) -> Result<Vec<Suggestion>, LatinDbError> {
+    let prefix = normalize(prefix);
+    if prefix.is_empty() {
+        return Ok(Vec::new());
+    }
+    let suggestions = diesel::sql_query(suggest_sql())
+        .bind::<Text, _>(prefix_pattern(&prefix))
+        .bind::<BigInt, _>(limit)
+        .bind::<BigInt, _>(offset)
+        .load(connection)?;
+    Ok(suggestions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_pattern() {
+        assert_eq!(prefix_pattern("serv"), "serv%");
+        assert_eq!(prefix_pattern("a_b%"), "a\\_b\\%%");
+    }
+
+    #[test]
+    fn test_suggest_sql_matches_folded_columns() {
+        let sql = suggest_sql();
+        assert!(sql.contains("WHERE latin_fold(form) LIKE $1"));
+        assert_eq!(
+            sql.matches("WHERE latin_fold(v.headword) LIKE $1").count(),
+            VOCAB_SOURCES.len()
+        );
+    }
+}
@@ -0,0 +1,205 @@
+use crate::enclitics::split_enclitics;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+
+/// What a token in a passage is.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenKind {
+    Word,
+    /// Arabic digits or an upper-case Roman numeral of two or more letters,
+    /// such as `XII`.
+    Numeral,
+}
+
+/// A word or numeral in a passage, with the punctuation stripped.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Token {
+    pub text: String,
+    pub kind: TokenKind,
+    /// The word was written with its final vowel (or vowel + m) elided,
+    /// as in `atqu'`, so `text` is incomplete.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub elided: bool,
+}
+
+impl Token {
+    fn new(text: String, kind: TokenKind, elided: bool) -> Self {
+        Token { text, kind, elided }
+    }
+
+    /// The spellings to try when looking this token up: the text itself,
+    /// then for an elided word the text with each ending it may have lost.
+    pub fn candidates(&self) -> Vec<String> {
+        let mut candidates = vec![self.text.clone()];
+        if self.elided {
+            for ending in ELIDED_ENDINGS {
+                candidates.push(format!("{}{}", self.text, ending));
+            }
+        }
+        candidates
+    }
+}
+
+/// What an elision mark may stand for, most likely first.
+const ELIDED_ENDINGS: [&str; 8] = ["e", "a", "o", "i", "u", "um", "am", "em"];
+
+fn is_apostrophe(c: char) -> bool {
+    matches!(c, '\'' | '\u{2019}')
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphabetic() || ('\u{0300}'..='\u{036f}').contains(&c)
+}
+
+lazy_static! {
+    /// A well-formed Roman numeral up to 3999, or the empty string.
+    static ref ROMAN_NUMERAL: Regex =
+        Regex::new("^M{0,3}(CM|CD|D?C{0,3})(XC|XL|L?X{0,3})(IX|IV|V?I{0,3})$").unwrap();
+}
+
+/// Single letters are left as words: `I`, `V` or `C` standing alone is more
+/// often a word or an initial than a number.
+fn is_roman_numeral(word: &str) -> bool {
+    word.chars().count() >= 2 && ROMAN_NUMERAL.is_match(word)
+}
+
+/// Whether `host` and the `-enclitic` hyphenated after it make one word, as
+/// in `populus-que`.
+fn joins_enclitic(host: &str, enclitic: &str) -> bool {
+    split_enclitics(&format!("{}{}", host, enclitic))
+        .iter()
+        .any(|compound| compound.stem == host)
+}
+
+/// Split a passage of Latin prose or verse into words and numerals.
+///
+/// Punctuation, brackets, quotation marks and line breaks are dropped, and
+/// hyphenated words are split, except that an enclitic hyphenated onto its
+/// word is joined back on (`populus-que` is `populusque`). An apostrophe
+/// ending a word marks elision (`atqu'`); one starting a word marks
+/// prodelision of `est` or `es`, so `'st` comes back as `est`. Enclitics
+/// are left attached here: `neque` and `itaque` are words in their own
+/// right, so they are only split off when the whole word is not found (see
+/// `split_enclitics`).
+pub fn tokenize(passage: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = passage.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            tokens.push(Token::new(text, TokenKind::Numeral, false));
+        } else if is_word_char(c) {
+            let start = i;
+            while i < chars.len() && is_word_char(chars[i]) {
+                i += 1;
+            }
+            let mut text: String = chars[start..i].iter().collect();
+            if chars.get(i) == Some(&'-') {
+                let mut end = i + 1;
+                while end < chars.len() && is_word_char(chars[end]) {
+                    end += 1;
+                }
+                let enclitic: String = chars[i + 1..end].iter().collect();
+                if !enclitic.is_empty() && joins_enclitic(&text, &enclitic) {
+                    text.push_str(&enclitic);
+                    i = end;
+                }
+            }
+            let elided = i < chars.len()
+                && is_apostrophe(chars[i])
+                && chars.get(i + 1).is_none_or(|next| !is_word_char(*next));
+            if elided {
+                i += 1;
+            }
+            let kind = if !elided && is_roman_numeral(&text) {
+                TokenKind::Numeral
+            } else {
+                TokenKind::Word
+            };
+            tokens.push(Token::new(text, kind, elided));
+        } else if is_apostrophe(c) && chars.get(i + 1).is_some_and(|next| is_word_char(*next)) {
+            let start = i + 1;
+            i = start;
+            while i < chars.len() && is_word_char(chars[i]) {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let text = match text.as_str() {
+                "st" => "est".to_string(),
+                "s" => "es".to_string(),
+                _ => text,
+            };
+            tokens.push(Token::new(text, TokenKind::Word, false));
+        } else {
+            i += 1;
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(passage: &str) -> Vec<String> {
+        tokenize(passage).into_iter().map(|t| t.text).collect()
+    }
+
+    #[test]
+    fn test_tokenize_prose_punctuation() {
+        assert_eq!(
+            texts("Gallia est omnis divisa in partes tres; quarum unam incolunt Belgae, aliam..."),
+            vec![
+                "Gallia", "est", "omnis", "divisa", "in", "partes", "tres", "quarum", "unam",
+                "incolunt", "Belgae", "aliam"
+            ]
+        );
+        assert_eq!(
+            texts("«senatusque» (populus-que) Graeco-Romanus"),
+            vec!["senatusque", "populusque", "Graeco", "Romanus"]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_elision() {
+        let tokens = tokenize("atqu' ibi, bonum'st");
+        assert_eq!(tokens[0].text, "atqu");
+        assert!(tokens[0].elided);
+        assert_eq!(tokens[0].candidates()[1], "atque");
+        assert!(!tokens[1].elided);
+        assert_eq!(tokens[2].text, "bonum");
+        assert_eq!(tokens[3].text, "est");
+    }
+
+    #[test]
+    fn test_tokenize_numerals() {
+        let tokens = tokenize("XII milites, 300 equites; Vi et armis I CIVIL");
+        let kinds: Vec<TokenKind> = tokens.iter().map(|t| t.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Numeral,
+                TokenKind::Word,
+                TokenKind::Numeral,
+                TokenKind::Word,
+                TokenKind::Word,
+                TokenKind::Word,
+                TokenKind::Word,
+                TokenKind::Word,
+                TokenKind::Word
+            ]
+        );
+        assert!(is_roman_numeral("MCMXCIV"));
+        assert!(is_roman_numeral("VI"));
+        assert!(!is_roman_numeral("IIII"));
+        assert!(!is_roman_numeral("VX"));
+    }
+}